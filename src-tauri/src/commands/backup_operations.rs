@@ -1,37 +1,153 @@
-use crate::error::Result;
-use serde::{Deserialize, Serialize};
+use crate::commands::{profile_operations, settings_operations};
+use crate::error::{AppError, Result};
+use crate::models::{
+    AppSettings, BackupDeleteResult, BackupInfo, BackupList, BackupRestoreResult, SaveRef, SaveRestoreResult, ValheimSave,
+};
+use crate::services::backup_service::{BackupService, BackupSources, RetentionPolicy};
+use crate::services::valheim_saves;
+use crate::state::AppState;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct BackupInfo {
-    pub id: String,
-    pub description: String,
-    pub timestamp: String,
-    pub size: u64,
+/// Backs up all profiles with their mod lists, the game's BepInEx configs, the configs of
+/// isolated profiles and, if `backup_include_saves` is set, all worlds and characters.
+#[tauri::command]
+pub async fn create_backup(app: AppHandle, description: Option<String>) -> Result<BackupInfo> {
+    tracing::info!("Creating backup: {:?}", description);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let description = description.filter(|d| !d.trim().is_empty()).unwrap_or_else(|| "Manual backup".to_string());
+    full_backup(&app, &settings, &description, false)
 }
 
+/// Restores a backup, taking a backup of the current state first. If the active profile is
+/// restored, its mods are linked again.
 #[tauri::command]
-pub async fn create_backup(description: Option<String>) -> Result<()> {
-    tracing::info!("Creating backup: {:?}", description);
+pub async fn restore_backup(app: AppHandle, state: State<'_, AppState>, backup_id: String) -> Result<BackupRestoreResult> {
+    tracing::info!("Restoring backup: {}", backup_id);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let app_data = profile_operations::app_data_dir(&app)?;
+    let shared_config = profile_operations::game_config_dir(&settings);
+    let saves_dir = settings.backup_include_saves.then(|| save_data_dir(&settings).ok()).flatten();
+    let sources = BackupSources { shared_config: shared_config.as_deref(), app_data: &app_data, saves_dir: saves_dir.as_deref() };
 
-    // TODO: Create backup
+    let mut result = BackupService::new(state.db.clone(), backup_dir(&app, &settings)?).restore(&backup_id, &sources)?;
 
-    Ok(())
+    let active: Option<String> = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        conn.query_row("SELECT id FROM profiles WHERE active = 1", [], |row| row.get(0)).ok()
+    };
+    if let Some(active) = active.filter(|id| result.profiles.contains(id)) {
+        if let Err(e) = profile_operations::switch_profile(app, state, active).await {
+            tracing::warn!("Backup restored, but re-linking the active profile failed: {}", e);
+            result.relink_error = Some(e.to_string());
+        }
+    }
+    Ok(result)
 }
 
 #[tauri::command]
-pub async fn restore_backup(backup_id: String) -> Result<()> {
-    tracing::info!("Restoring backup: {}", backup_id);
+pub async fn list_backups(app: AppHandle, state: State<'_, AppState>) -> Result<BackupList> {
+    tracing::info!("Listing backups");
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    BackupService::new(state.db.clone(), backup_dir(&app, &settings)?).list()
+}
 
-    // TODO: Restore from backup
+/// Deletes a backup along with the stored files only it used.
+#[tauri::command]
+pub async fn delete_backup(app: AppHandle, state: State<'_, AppState>, backup_id: String) -> Result<BackupDeleteResult> {
+    tracing::info!("Deleting backup: {}", backup_id);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    BackupService::new(state.db.clone(), backup_dir(&app, &settings)?).delete(&backup_id)
+}
 
-    Ok(())
+/// Worlds and characters in Valheim's data folder.
+#[tauri::command]
+pub async fn list_valheim_saves(app: AppHandle) -> Result<Vec<ValheimSave>> {
+    let settings = settings_operations::load_settings(app).await.map_err(AppError::Custom)?;
+    valheim_saves::list(&save_data_dir(&settings)?)
 }
 
+/// Backs up the selected worlds and characters, or all of them when `saves` is not given.
 #[tauri::command]
-pub async fn list_backups() -> Result<Vec<BackupInfo>> {
-    tracing::info!("Listing backups");
+pub async fn backup_valheim_saves(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    saves: Option<Vec<SaveRef>>,
+    description: Option<String>,
+) -> Result<BackupInfo> {
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let data_dir = save_data_dir(&settings)?;
+    let saves = match saves {
+        Some(saves) => saves,
+        None => valheim_saves::list(&data_dir)?
+            .into_iter()
+            .map(|s| SaveRef { kind: s.kind, name: s.name, legacy: s.legacy })
+            .collect(),
+    };
+    tracing::info!("Backing up {} Valheim saves", saves.len());
 
-    // TODO: List all backups
+    let description = description.filter(|d| !d.trim().is_empty()).unwrap_or_else(|| "Worlds and characters".to_string());
+    BackupService::new(state.db.clone(), backup_dir(&app, &settings)?).backup_saves(&description, &data_dir, &saves)
+}
 
-    Ok(vec![])
-}
\ No newline at end of file
+/// Worlds and characters a backup holds.
+#[tauri::command]
+pub async fn list_backup_saves(app: AppHandle, state: State<'_, AppState>, backup_id: String) -> Result<Vec<ValheimSave>> {
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    BackupService::new(state.db.clone(), backup_dir(&app, &settings)?).list_saves(&backup_id)
+}
+
+/// Restores individual worlds and characters from a backup, after backing up the current
+/// files of each. Refused while the game runs, since it rewrites its saves on exit.
+#[tauri::command]
+pub async fn restore_valheim_saves(app: AppHandle, state: State<'_, AppState>, backup_id: String, saves: Vec<SaveRef>) -> Result<SaveRestoreResult> {
+    tracing::info!("Restoring {} saves from backup {}", saves.len(), backup_id);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let running: bool = conn.query_row("SELECT EXISTS(SELECT 1 FROM play_sessions WHERE ended_at IS NULL)", [], |row| row.get(0))?;
+        if running {
+            return Err(AppError::Validation("Close Valheim before restoring worlds or characters".to_string()));
+        }
+    }
+    BackupService::new(state.db.clone(), backup_dir(&app, &settings)?).restore_saves(&backup_id, &save_data_dir(&settings)?, &saves)
+}
+
+/// Takes a full backup with the sources from `settings`. Saves are skipped rather than
+/// failing the backup when Valheim's data folder cannot be found.
+pub(crate) fn full_backup(app: &AppHandle, settings: &AppSettings, description: &str, automatic: bool) -> Result<BackupInfo> {
+    let app_data = profile_operations::app_data_dir(app)?;
+    let shared_config = profile_operations::game_config_dir(settings);
+    let saves_dir = settings.backup_include_saves.then(|| save_data_dir(settings).ok()).flatten();
+    let sources = BackupSources { shared_config: shared_config.as_deref(), app_data: &app_data, saves_dir: saves_dir.as_deref() };
+    let state = app.state::<AppState>();
+    BackupService::new(state.db.clone(), backup_dir(app, settings)?).create(description, &sources, automatic)
+}
+
+/// Retention rules for automatic backups from the settings.
+pub(crate) fn retention_policy(settings: &AppSettings) -> RetentionPolicy {
+    RetentionPolicy {
+        keep_last: settings.backup_keep_last as usize,
+        keep_daily_days: i64::from(settings.backup_keep_daily_days),
+        keep_weekly_weeks: i64::from(settings.backup_keep_weekly_weeks),
+        max_total_bytes: settings.backup_max_total_mb.saturating_mul(1024 * 1024),
+    }
+}
+
+/// Folder backups are written to: the configured `backup_path`, or `backups` in app data.
+pub(crate) fn backup_dir(app: &AppHandle, settings: &AppSettings) -> Result<PathBuf> {
+    if settings.backup_path.is_empty() {
+        Ok(profile_operations::app_data_dir(app)?.join("backups"))
+    } else {
+        Ok(PathBuf::from(&settings.backup_path))
+    }
+}
+
+/// Valheim's data folder: the configured `valheim_data_path`, or the detected one.
+fn save_data_dir(settings: &AppSettings) -> Result<PathBuf> {
+    if !settings.valheim_data_path.is_empty() {
+        return Ok(PathBuf::from(&settings.valheim_data_path));
+    }
+    valheim_saves::data_dir()
+        .ok_or_else(|| AppError::Custom("Valheim's save folder was not found, set it in the settings".to_string()))
+}
@@ -0,0 +1,178 @@
+use crate::commands::{profile_operations, settings_operations};
+use crate::error::{AppError, Result};
+use crate::models::{AppSettings, BundleConfig, ImportPlan, ImportResult, ProfileBundleInfo, ProfilePatch};
+use crate::services::profile_bundle::{self, Bundle, BundleManifest, BundleMod};
+use crate::services::profile_import::{self, ImportEntry};
+use crate::services::profile_lock;
+use crate::services::profile_manager::{self, ProfileManager};
+use crate::state::AppState;
+use crate::utils::file_ops;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+/// Config files of a profile that can be included in a bundle.
+#[tauri::command]
+pub async fn list_profile_configs(app: AppHandle, profile_id: String) -> Result<Vec<String>> {
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    match profile_operations::profile_config_dir(&app, &settings, &profile_id)? {
+        Some(dir) => profile_bundle::list_configs(&dir),
+        None => Ok(vec![]),
+    }
+}
+
+/// Writes a profile bundle (zip) with the profile manifest, a lockfile and the chosen
+/// config files (`configs` as returned by `list_profile_configs`, all when omitted).
+#[tauri::command]
+pub async fn export_profile_bundle(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    profile_id: String,
+    path: String,
+    configs: Option<Vec<String>>,
+) -> Result<()> {
+    tracing::info!("Exporting profile {} as bundle to {}", profile_id, path);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+
+    let manifest = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let profile = profile_manager::load_profile(&conn, &profile_id)?;
+        let mut stmt = conn.prepare("SELECT mod_id, enabled FROM profile_mods WHERE profile_id = ?1 ORDER BY mod_id")?;
+        let mods = stmt
+            .query_map([&profile_id], |row| Ok(BundleMod { mod_id: row.get(0)?, enabled: row.get(1)? }))?
+            .collect::<rusqlite::Result<_>>()?;
+        BundleManifest::new(&profile, mods)
+    };
+
+    // A fresh lock reflects the profile as exported; fall back to the saved one if some
+    // packages cannot be locked right now
+    let lock = match profile_lock::build(&state.db, &profile_id, Path::new(&settings.repository_path)).await {
+        Ok(lock) => Some(lock),
+        Err(e) => {
+            tracing::warn!("Could not lock profile {} for export, using saved lockfile: {}", profile_id, e);
+            profile_lock::read(&profile_lock::lock_path(&profile_operations::app_data_dir(&app)?, &profile_id))?
+        }
+    };
+
+    let config_dir = profile_operations::profile_config_dir(&app, &settings, &profile_id)?;
+    let data = profile_bundle::write(&manifest, lock.as_ref(), config_dir.as_deref(), configs.as_deref())?;
+    fs::write(&path, data)?;
+    Ok(())
+}
+
+/// Reads a bundle and reports what importing it would install and which bundled configs
+/// would replace different existing files.
+#[tauri::command]
+pub async fn inspect_profile_bundle(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<ProfileBundleInfo> {
+    let bundle = profile_bundle::read(&fs::read(&path)?)?;
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let destination = shared_config_dir(&settings);
+
+    let plan = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        profile_import::resolve(&conn, &import_entries(&bundle), Path::new(&settings.repository_path))?
+    };
+    let configs = bundle
+        .configs
+        .iter()
+        .map(|(relative, content)| BundleConfig {
+            path: config_key(relative),
+            conflict: destination.as_ref().is_some_and(|dir| differs(&dir.join(relative), content)),
+        })
+        .collect();
+
+    Ok(ProfileBundleInfo {
+        name: bundle.manifest.name,
+        description: bundle.manifest.description,
+        has_lock: bundle.lock.is_some(),
+        plan,
+        configs,
+    })
+}
+
+/// Imports a bundle as a new profile. Bundled configs are written where the new profile
+/// reads them; existing files that differ are only replaced when listed in `overwrite`.
+#[tauri::command]
+pub async fn import_profile_bundle(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    new_name: Option<String>,
+    overwrite: Vec<String>,
+) -> Result<ImportResult> {
+    tracing::info!("Importing profile bundle from {}", path);
+    let bundle = profile_bundle::read(&fs::read(&path)?)?;
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    if settings.repository_path.is_empty() {
+        return Err(AppError::Custom("Repository path is not configured".to_string()));
+    }
+
+    let name = new_name.unwrap_or_else(|| bundle.manifest.name.clone());
+    let plan: ImportPlan = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        profile_manager::validate_name(&conn, &name, None)?;
+        profile_import::resolve(&conn, &import_entries(&bundle), Path::new(&settings.repository_path))?
+    };
+    let mut result = profile_operations::install_plan(&state, &settings.repository_path, plan, name).await?;
+    let profile_id = result.profile.id.clone();
+
+    let patch = ProfilePatch {
+        description: Some(bundle.manifest.description.clone()),
+        icon: Some(bundle.manifest.icon.clone()),
+        color: Some(bundle.manifest.color.clone()).filter(|c| crate::utils::validation::is_valid_color(c)),
+        ..Default::default()
+    };
+    result.profile = ProfileManager::new(state.db.clone()).update(&profile_id, patch)?;
+
+    let app_data = profile_operations::app_data_dir(&app)?;
+    if let Some(lock) = &bundle.lock {
+        profile_lock::write(&profile_lock::lock_path(&app_data, &profile_id), lock)?;
+    }
+
+    if let Some(dir) = profile_operations::profile_config_dir(&app, &settings, &profile_id)? {
+        let files: Vec<(PathBuf, Vec<u8>)> = bundle
+            .configs
+            .into_iter()
+            .filter(|(relative, content)| !differs(&dir.join(relative), content) || overwrite.contains(&config_key(relative)))
+            .collect();
+        file_ops::write_files(&dir, &files)?;
+    }
+
+    Ok(result)
+}
+
+/// Entries to install: the exact locked versions when the bundle has a lock.
+fn import_entries(bundle: &Bundle) -> Vec<ImportEntry> {
+    match &bundle.lock {
+        Some(lock) => lock
+            .packages
+            .iter()
+            .map(|p| ImportEntry { name: p.mod_id.clone(), enabled: p.enabled })
+            .collect(),
+        None => bundle
+            .manifest
+            .mods
+            .iter()
+            .map(|m| ImportEntry { name: m.mod_id.clone(), enabled: m.enabled })
+            .collect(),
+    }
+}
+
+/// Config folder in the game install. With isolated profiles a new profile gets a fresh
+/// folder of its own, so there is nothing to conflict with.
+fn shared_config_dir(settings: &AppSettings) -> Option<PathBuf> {
+    if settings.isolated_profiles {
+        None
+    } else {
+        profile_operations::game_config_dir(settings)
+    }
+}
+
+fn config_key(relative: &Path) -> String {
+    relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+/// True if a file exists at `path` with other content.
+fn differs(path: &Path, content: &[u8]) -> bool {
+    fs::read(path).is_ok_and(|existing| existing != content)
+}
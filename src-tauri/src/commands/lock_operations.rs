@@ -0,0 +1,117 @@
+use crate::commands::{mod_operations, profile_operations, settings_operations};
+use crate::error::{AppError, Result};
+use crate::models::{LockMismatch, LockMismatchKind, LockSyncResult, LockedPackage, ProfileLock};
+use crate::services::{mod_installer, profile_lock};
+use crate::state::AppState;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+/// Writes a lockfile pinning the profile's mods to the files currently installed.
+#[tauri::command]
+pub async fn lock_profile(app: AppHandle, state: State<'_, AppState>, profile_id: String) -> Result<ProfileLock> {
+    tracing::info!("Locking profile: {}", profile_id);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    if settings.repository_path.is_empty() {
+        return Err(AppError::Custom("Repository path is not configured".to_string()));
+    }
+
+    let lock = profile_lock::build(&state.db, &profile_id, Path::new(&settings.repository_path)).await?;
+    profile_lock::write(&profile_lock::lock_path(&profile_operations::app_data_dir(&app)?, &profile_id), &lock)?;
+    Ok(lock)
+}
+
+#[tauri::command]
+pub async fn get_profile_lock(app: AppHandle, profile_id: String) -> Result<Option<ProfileLock>> {
+    profile_lock::read(&profile_lock::lock_path(&profile_operations::app_data_dir(&app)?, &profile_id))
+}
+
+/// Lists where the profile or its installed files have drifted from the lockfile.
+#[tauri::command]
+pub async fn check_profile_lock(app: AppHandle, state: State<'_, AppState>, profile_id: String) -> Result<Vec<LockMismatch>> {
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let lock = read_lock(&app, &profile_id)?;
+
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    profile_lock::check(&conn, &profile_id, &lock, Path::new(&settings.repository_path))
+}
+
+/// Brings a profile back to its lockfile: the mod list is reset to the locked packages,
+/// missing ones are downloaded and checked against the locked archive hash, and installed
+/// ones whose files changed are reinstalled.
+#[tauri::command]
+pub async fn sync_profile_lock(app: AppHandle, state: State<'_, AppState>, profile_id: String) -> Result<LockSyncResult> {
+    tracing::info!("Syncing profile {} to its lockfile", profile_id);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    if settings.repository_path.is_empty() {
+        return Err(AppError::Custom("Repository path is not configured".to_string()));
+    }
+    let repository_path = Path::new(&settings.repository_path);
+    let lock = read_lock(&app, &profile_id)?;
+
+    let mut result = LockSyncResult::default();
+    for package in &lock.packages {
+        let mismatch = profile_lock::files_mismatch(package, repository_path)?;
+        if mismatch.is_none() {
+            result.verified.push(package.mod_id.clone());
+            continue;
+        }
+        if package.download_url.is_empty() {
+            result.failed.push(format!("{}: no download URL", package.mod_id));
+            continue;
+        }
+
+        let expected = Some(package.sha256.as_str()).filter(|h| !h.is_empty());
+        let installed = if mismatch == Some(LockMismatchKind::Modified) {
+            reinstall(&state, repository_path, package, expected).await
+        } else {
+            mod_operations::install_single_mod(&state, &settings.repository_path, &package.mod_id, &package.download_url, expected)
+                .await
+                .and_then(|_| profile_lock::files_mismatch(package, repository_path))
+        };
+        match installed {
+            Ok(None) if mismatch == Some(LockMismatchKind::Modified) => result.repaired.push(package.mod_id.clone()),
+            Ok(None) => result.installed.push(package.mod_id.clone()),
+            Ok(Some(_)) => result.failed.push(format!("{}: installed files do not match the lock", package.mod_id)),
+            Err(e) => result.failed.push(format!("{}: {}", package.mod_id, e)),
+        }
+    }
+
+    let active = {
+        let mut conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        profile_lock::apply_to_profile(&mut conn, &profile_id, &lock)?;
+        conn.query_row("SELECT active FROM profiles WHERE id = ?1", [&profile_id], |row| row.get::<_, bool>(0))?
+    };
+    if active {
+        if let Err(e) = profile_operations::switch_profile(app, state, profile_id).await {
+            tracing::warn!("Profile synced, but re-linking its mods failed: {}", e);
+            result.relink_error = Some(e.to_string());
+        }
+    }
+
+    Ok(result)
+}
+
+/// Reinstalls a package whose files were modified. Every profile shares the installed copy,
+/// so the fresh one is downloaded next to it and only replaces it once it matches the lock.
+async fn reinstall(state: &AppState, repository_path: &Path, package: &LockedPackage, expected: Option<&str>) -> Result<Option<LockMismatchKind>> {
+    let staging = mod_installer::staging_root(repository_path);
+    // A copy left over from an interrupted sync would be taken as already installed
+    mod_installer::discard_staged(repository_path, &package.mod_id)?;
+
+    let staged = mod_operations::install_single_mod(state, &staging.to_string_lossy(), &package.mod_id, &package.download_url, expected)
+        .await
+        .and_then(|_| profile_lock::files_mismatch(package, &staging));
+    if let Ok(None) = staged {
+        return mod_installer::replace_with_staged(repository_path, &package.mod_id).map(|_| None);
+    }
+
+    if let Err(e) = mod_installer::discard_staged(repository_path, &package.mod_id) {
+        tracing::warn!("Could not remove the staged copy of {}: {}", package.mod_id, e);
+    }
+    staged
+}
+
+fn read_lock(app: &AppHandle, profile_id: &str) -> Result<ProfileLock> {
+    profile_lock::read(&profile_lock::lock_path(&profile_operations::app_data_dir(app)?, profile_id))?
+        .ok_or_else(|| AppError::Validation(format!("Profile {} has no lockfile", profile_id)))
+}
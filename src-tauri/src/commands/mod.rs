@@ -1,5 +1,8 @@
 pub mod mod_operations;
 pub mod profile_operations;
+pub mod lock_operations;
+pub mod r2modman_operations;
+pub mod bundle_operations;
 pub mod system_operations;
 pub mod update_operations;
 pub mod backup_operations;
@@ -1,23 +1,31 @@
 use crate::error::{AppError, Result};
 use crate::models::ModInfo;
 use crate::services::thunderstore_service::ThunderstoreService;
+use crate::services::update_checker::UpdateChecker;
 use std::path::Path;
+use rusqlite::Connection;
 use std::fs;
 use std::io::Cursor;
 use walkdir::WalkDir;
 use serde::Deserialize;
 use tauri::State;
 use crate::state::AppState;
-use sha2::{Sha256, Digest};
+use crate::utils::{file_ops, hash};
 use futures::stream::{self, StreamExt};
 
 #[derive(Debug, Deserialize)]
-struct Manifest {
-    name: String,
-    version_number: String,
-    website_url: Option<String>,
-    description: Option<String>,
-    dependencies: Option<Vec<String>>,
+pub(crate) struct Manifest {
+    pub name: String,
+    pub version_number: String,
+    pub website_url: Option<String>,
+    pub description: Option<String>,
+    pub dependencies: Option<Vec<String>>,
+}
+
+/// Reads the Thunderstore `manifest.json` of a package folder, if it has a valid one.
+pub(crate) fn read_manifest(dir: &Path) -> Option<Manifest> {
+    let content = fs::read_to_string(dir.join("manifest.json")).ok()?;
+    serde_json::from_str(&content).ok()
 }
 
 #[tauri::command]
@@ -42,39 +50,34 @@ pub async fn scan_mods(repository_path: String) -> Result<Vec<ModInfo>> {
         let entry = entry?;
         let path = entry.path();
         if path.is_dir() {
-            let manifest_path = path.join("manifest.json");
-            if manifest_path.exists() {
-                if let Ok(content) = fs::read_to_string(&manifest_path) {
-                    if let Ok(manifest) = serde_json::from_str::<Manifest>(&content) {
-                        let id = path.file_name().unwrap().to_string_lossy().to_string();
-                        // Try to parse author from ID (Author-Name-Version) or just directory name
-                        // This is heuristic.
-                        let parts: Vec<&str> = id.split('-').collect();
-                        let author = if parts.len() >= 2 { parts[0].to_string() } else { "Unknown".to_string() };
-
-                        // Calculate size
-                        let size = WalkDir::new(&path).into_iter().filter_map(|e| e.ok()).map(|e| e.metadata().map(|m| m.len()).unwrap_or(0)).sum();
-
-                        mods.push(ModInfo {
-                            id: id.clone(),
-                            name: manifest.name,
-                            version: manifest.version_number,
-                            author,
-                            description: manifest.description.unwrap_or_default(),
-                            icon: None, // TODO: Load icon.png if exists
-                            size,
-                            installed: true,
-                            enabled: false, // This depends on profile, scan_mods just lists repo?
-                            dependencies: manifest.dependencies.unwrap_or_default(),
-                            categories: vec![],
-                            download_url: None,
-                            website_url: manifest.website_url,
-                            rating: None,
-                            downloads: None,
-                            last_updated: String::new(), // Metadata doesn't have this
-                        });
-                    }
-                }
+            if let Some(manifest) = read_manifest(&path) {
+                let id = path.file_name().unwrap().to_string_lossy().to_string();
+                // Try to parse author from ID (Author-Name-Version) or just directory name
+                // This is heuristic.
+                let parts: Vec<&str> = id.split('-').collect();
+                let author = if parts.len() >= 2 { parts[0].to_string() } else { "Unknown".to_string() };
+
+                // Calculate size
+                let size = WalkDir::new(&path).into_iter().filter_map(|e| e.ok()).map(|e| e.metadata().map(|m| m.len()).unwrap_or(0)).sum();
+
+                mods.push(ModInfo {
+                    id: id.clone(),
+                    name: manifest.name,
+                    version: manifest.version_number,
+                    author,
+                    description: manifest.description.unwrap_or_default(),
+                    icon: None, // TODO: Load icon.png if exists
+                    size,
+                    installed: true,
+                    enabled: false, // This depends on profile, scan_mods just lists repo?
+                    dependencies: manifest.dependencies.unwrap_or_default(),
+                    categories: vec![],
+                    download_url: None,
+                    website_url: manifest.website_url,
+                    rating: None,
+                    downloads: None,
+                    last_updated: String::new(), // Metadata doesn't have this
+                });
             }
         }
     }
@@ -82,17 +85,12 @@ pub async fn scan_mods(repository_path: String) -> Result<Vec<ModInfo>> {
     Ok(mods)
 }
 
-fn verify_checksum(content: &[u8], expected_hash: &str) -> Result<()> {
+fn verify_checksum(computed_hash: &str, expected_hash: &str) -> Result<()> {
     if expected_hash.is_empty() {
         return Ok(());
     }
-    let mut hasher = Sha256::new();
-    hasher.update(content);
-    let result = hasher.finalize();
-    let computed_hash = hex::encode(result);
-
-    if computed_hash != expected_hash {
-        return Err(AppError::ChecksumMismatch(expected_hash.to_string(), computed_hash));
+    if !computed_hash.eq_ignore_ascii_case(expected_hash) {
+        return Err(AppError::ChecksumMismatch(expected_hash.to_string(), computed_hash.to_string()));
     }
     Ok(())
 }
@@ -160,8 +158,9 @@ pub async fn install_single_mod(
         let response = reqwest::get(url).await?;
         let content = response.bytes().await?;
 
+        let archive_hash = hash::sha256_hex(&content);
         if let Some(hash) = expected_hash {
-            verify_checksum(&content, hash)?;
+            verify_checksum(&archive_hash, hash)?;
         }
 
         let reader = Cursor::new(content);
@@ -191,6 +190,11 @@ pub async fn install_single_mod(
 
         // Parse manifest and update DB
         update_db_from_manifest(state, &target_dir, mod_id, url)?;
+        record_archive(state, mod_id, url, &archive_hash)?;
+        // The changelog only feeds update reports; the package is installed either way
+        if let Err(e) = UpdateChecker::new(state.db.clone()).cache_changelog_from_dir(mod_id, &target_dir) {
+            tracing::warn!("Could not cache the changelog of {}: {}", mod_id, e);
+        }
 
     } else {
         tracing::info!("Mod {} already installed.", mod_id);
@@ -198,7 +202,18 @@ pub async fn install_single_mod(
     Ok(())
 }
 
-fn update_db_from_manifest(state: &AppState, target_dir: &Path, mod_id: &str, url: &str) -> Result<()> {
+/// Remembers where an installed version was downloaded from and the SHA-256 of the archive,
+/// so lockfiles can pin it without downloading it again.
+fn record_archive(state: &AppState, mod_id: &str, url: &str, sha256: &str) -> Result<()> {
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    conn.execute(
+        "INSERT OR REPLACE INTO package_archives (mod_id, download_url, sha256, installed_at) VALUES (?1, ?2, ?3, ?4)",
+        (mod_id, url, sha256, chrono::Utc::now().to_rfc3339()),
+    )?;
+    Ok(())
+}
+
+pub fn update_db_from_manifest(state: &AppState, target_dir: &Path, mod_id: &str, url: &str) -> Result<()> {
     let manifest_path = target_dir.join("manifest.json");
     if manifest_path.exists() {
         if let Ok(content) = fs::read_to_string(&manifest_path) {
@@ -249,6 +264,18 @@ fn update_db_from_manifest(state: &AppState, target_dir: &Path, mod_id: &str, ur
     Ok(())
 }
 
+/// Removes the rows `update_db_from_manifest` created for an installed version. Catalog rows
+/// are keyed by package name and are left alone; the dependents go first so the foreign keys hold.
+pub fn remove_installed_from_db(conn: &Connection, mod_id: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM mod_dependencies WHERE version_full_name IN (SELECT full_name FROM mod_versions WHERE mod_id = ?1)",
+        [mod_id],
+    )?;
+    conn.execute("DELETE FROM mod_versions WHERE mod_id = ?1", [mod_id])?;
+    conn.execute("DELETE FROM mods WHERE id = ?1", [mod_id])?;
+    Ok(())
+}
+
 fn get_dependencies_from_db(state: &AppState, mod_id: &str) -> Result<Vec<String>> {
     let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
     let mut stmt = conn.prepare("SELECT dependency_id FROM mod_dependencies WHERE version_full_name = ?")?;
@@ -348,17 +375,7 @@ pub async fn enable_mod(repository_path: String, game_plugins_path: String, mod_
         return Ok(());
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        match std::os::windows::fs::symlink_dir(&source_dir, &target_dir) {
-            Ok(_) => Ok(()),
-            Err(_) => link_dir_contents(&source_dir, &target_dir).map_err(AppError::Io)
-        }?;
-    }
-    #[cfg(unix)]
-    {
-        std::os::unix::fs::symlink(&source_dir, &target_dir)?;
-    }
+    file_ops::link_dir(&source_dir, &target_dir)?;
 
     Ok(())
 }
@@ -367,31 +384,6 @@ pub async fn enable_mod(repository_path: String, game_plugins_path: String, mod_
 pub async fn disable_mod(game_plugins_path: String, mod_id: String) -> Result<()> {
     tracing::info!("Disabling mod: {}", mod_id);
     let target_dir = Path::new(&game_plugins_path).join(&mod_id);
-    if target_dir.exists() {
-        #[cfg(unix)]
-        fs::remove_file(&target_dir).or_else(|_| fs::remove_dir_all(&target_dir))?;
-
-        #[cfg(target_os = "windows")]
-        fs::remove_dir_all(&target_dir)?;
-    }
-    Ok(())
-}
-
-// Helper for recursive hardlinking (Windows fallback)
-#[cfg(target_os = "windows")]
-fn link_dir_contents(src: &Path, dst: &Path) -> std::io::Result<()> {
-    fs::create_dir_all(dst)?;
-    for entry in WalkDir::new(src) {
-        let entry = entry?;
-        let path = entry.path();
-        let rel_path = path.strip_prefix(src).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        let target_path = dst.join(rel_path);
-
-        if path.is_dir() {
-            fs::create_dir_all(&target_path)?;
-        } else {
-            fs::hard_link(path, &target_path)?;
-        }
-    }
+    file_ops::unlink_dir(&target_dir)?;
     Ok(())
 }
@@ -1,10 +1,23 @@
-use crate::{error::{Result, AppError}, models::Profile, state::AppState};
-use tauri::State;
+use crate::{error::{Result, AppError}, models::{AppSettings, ConfigKey, ImportPlan, ImportResult, Profile, ProfileDetails, ProfileDiff, ProfileHealth, ProfileHistoryEntry, ProfileMergeResult, ProfilePatch, ProfileRevertResult, ProfileSwitchResult, ProfileUpdateResult, ServerProfileResult, ProfileTemplate}, state::AppState};
+use crate::commands::{backup_operations, mod_operations, settings_operations, system_operations};
+use crate::services::backup_service::BackupService;
+use crate::services::mod_installer;
+use crate::services::profile_diff;
+use crate::services::profile_health::{self, HealthTarget};
+use crate::services::profile_history;
+use crate::services::profile_import::{self, ImportEntry};
+use crate::services::profile_manager::{self, ProfileManager};
+use crate::services::server_import;
+use rusqlite::OptionalExtension;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
 use std::io::{Read, Write};
 use base64::{Engine as _, engine::general_purpose};
 use flate2::write::GzEncoder;
 use flate2::read::GzDecoder;
 use flate2::Compression;
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,6 +38,9 @@ pub async fn create_profile(state: State<'_, AppState>, name: String) -> Result<
     let id = uuid::Uuid::new_v4().to_string();
     let now = chrono::Utc::now().to_rfc3339();
 
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    let name = profile_manager::validate_name(&conn, &name, None)?;
+
     let profile = Profile {
         id: id.clone(),
         name: name.clone(),
@@ -38,7 +54,6 @@ pub async fn create_profile(state: State<'_, AppState>, name: String) -> Result<
         play_time: 0,
     };
 
-    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
     conn.execute(
         "INSERT INTO profiles (id, name, description, icon, color, active, created, last_used, play_time)
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
@@ -59,62 +74,274 @@ pub async fn create_profile(state: State<'_, AppState>, name: String) -> Result<
 }
 
 #[tauri::command]
-pub async fn update_profile(id: String, updates: serde_json::Value) -> Result<()> {
+pub async fn update_profile(app: AppHandle, state: State<'_, AppState>, id: String, updates: ProfilePatch) -> Result<ProfileUpdateResult> {
     tracing::info!("Updating profile: {}", id);
+    let mods_changed = updates.mods.is_some();
+    let profile = ProfileManager::new(state.db.clone()).update(&id, updates)?;
+
+    // Keep the plugins folder in step with the new mod list of the active profile. The change
+    // is saved either way, so a failure is reported alongside the updated profile.
+    let mut relink_error = None;
+    if mods_changed && profile.active {
+        if let Err(e) = switch_profile(app, state, id).await {
+            tracing::warn!("Profile updated, but re-linking its mods failed: {}", e);
+            relink_error = Some(e.to_string());
+        }
+    }
+
+    Ok(ProfileUpdateResult { profile, relink_error })
+}
+
+/// Deletes a profile after backing it up. The active profile is only deleted when
+/// `switch_to` names another profile to activate first.
+#[tauri::command]
+pub async fn delete_profile(app: AppHandle, state: State<'_, AppState>, id: String, switch_to: Option<String>) -> Result<()> {
+    tracing::info!("Deleting profile: {}", id);
+    let profile = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        profile_manager::load_profile(&conn, &id)?
+    };
+
+    let switch_to = match switch_to {
+        Some(other) if profile.active && other != id => Some(other),
+        _ if profile.active => {
+            return Err(AppError::Validation(
+                "The active profile cannot be deleted, switch to another profile first".to_string(),
+            ))
+        }
+        _ => None,
+    };
+
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let app_data = app_data_dir(&app)?;
+    let bepinex_root = profile_manager::profile_bepinex_dir(&app_data, &id);
+
+    // Nothing is removed or switched away from unless the backup succeeded
+    BackupService::new(state.db.clone(), backup_operations::backup_dir(&app, &settings)?).backup_profile(
+        &id,
+        Some(bepinex_root.as_path()).filter(|root| root.exists()),
+        &format!("Before deleting profile '{}'", profile.name),
+    )?;
+
+    if let Some(other) = switch_to {
+        switch_profile(app.clone(), state.clone(), other).await?;
+    }
+
+    ProfileManager::new(state.db.clone()).delete(&id)?;
 
-    // TODO: Implement profile update
+    let profile_dir = app_data.join("profiles").join(&id);
+    if profile_dir.exists() {
+        if let Err(e) = fs::remove_dir_all(&profile_dir) {
+            tracing::warn!("Profile {} deleted, but removing {} failed: {}", id, profile_dir.display(), e);
+        }
+    }
 
     Ok(())
 }
 
+/// Copies a profile, including its mod list and, for isolated profiles, its configs.
 #[tauri::command]
-pub async fn delete_profile(id: String) -> Result<()> {
-    tracing::info!("Deleting profile: {}", id);
+pub async fn duplicate_profile(app: AppHandle, state: State<'_, AppState>, id: String, name: String) -> Result<Profile> {
+    tracing::info!("Duplicating profile {} as {}", id, name);
+    let manager = ProfileManager::new(state.db.clone());
+    let profile = manager.duplicate(&id, &name)?;
+
+    let app_data = app_data_dir(&app)?;
+    let copied = profile_manager::copy_config(
+        &profile_manager::profile_bepinex_dir(&app_data, &id),
+        &profile_manager::profile_bepinex_dir(&app_data, &profile.id),
+    );
+    if let Err(e) = copied {
+        manager.delete(&profile.id)?;
+        return Err(e);
+    }
+
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn save_profile_as_template(app: AppHandle, state: State<'_, AppState>, profile_id: String, name: String) -> Result<ProfileTemplate> {
+    tracing::info!("Saving profile {} as template {}", profile_id, name);
+    let manager = ProfileManager::new(state.db.clone());
+    let template = manager.save_template(&profile_id, &name)?;
+
+    let app_data = app_data_dir(&app)?;
+    let copied = profile_manager::copy_config(
+        &profile_manager::profile_bepinex_dir(&app_data, &profile_id),
+        &profile_manager::template_dir(&app_data, &template.id),
+    );
+    if let Err(e) = copied {
+        manager.delete_template(&template.id)?;
+        return Err(e);
+    }
 
-    // TODO: Implement profile deletion
+    Ok(template)
+}
 
+#[tauri::command]
+pub async fn create_profile_from_template(app: AppHandle, state: State<'_, AppState>, template_id: String, name: String) -> Result<Profile> {
+    tracing::info!("Creating profile {} from template {}", name, template_id);
+    let manager = ProfileManager::new(state.db.clone());
+    let profile = manager.create_from_template(&template_id, &name)?;
+
+    let app_data = app_data_dir(&app)?;
+    let copied = profile_manager::copy_config(
+        &profile_manager::template_dir(&app_data, &template_id),
+        &profile_manager::profile_bepinex_dir(&app_data, &profile.id),
+    );
+    if let Err(e) = copied {
+        manager.delete(&profile.id)?;
+        return Err(e);
+    }
+
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn list_profile_templates(state: State<'_, AppState>) -> Result<Vec<ProfileTemplate>> {
+    ProfileManager::new(state.db.clone()).list_templates()
+}
+
+#[tauri::command]
+pub async fn delete_profile_template(app: AppHandle, state: State<'_, AppState>, id: String) -> Result<()> {
+    tracing::info!("Deleting profile template: {}", id);
+    ProfileManager::new(state.db.clone()).delete_template(&id)?;
+
+    let dir = profile_manager::template_dir(&app_data_dir(&app)?, &id);
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
     Ok(())
 }
 
 #[tauri::command]
-pub async fn switch_profile(id: String) -> Result<()> {
+pub async fn switch_profile(app: AppHandle, state: State<'_, AppState>, id: String) -> Result<ProfileSwitchResult> {
     tracing::info!("Switching to profile: {}", id);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
 
-    // TODO: Implement profile switching
-    // 1. Disable current profile mods
-    // 2. Enable new profile mods
-    // 3. Update database
+    if settings.repository_path.is_empty() {
+        return Err(AppError::Custom("Repository path is not configured".to_string()));
+    }
 
-    Ok(())
+    // Isolated profiles each link into their own BepInEx root, shared ones into the game folder
+    let plugins_dir = if settings.isolated_profiles {
+        let valheim_path = system_operations::detect_valheim_path(app.clone()).await?;
+        profile_manager::ensure_bepinex_root(&app_data_dir(&app)?, &id, Path::new(&valheim_path))?.join("plugins")
+    } else {
+        mod_installer::plugins_dir(&settings)
+            .ok_or_else(|| AppError::Custom("Valheim or BepInEx path is not configured".to_string()))?
+    };
+
+    let result = ProfileManager::new(state.db.clone()).switch(&id, Path::new(&settings.repository_path), &plugins_dir)?;
+    tracing::info!(
+        "Switched to profile {}: {} linked, {} unlinked, {} missing",
+        id, result.linked.len(), result.unlinked.len(), result.missing.len()
+    );
+    Ok(result)
 }
 
+/// BepInEx folder holding a profile's config and plugins: its own root when profiles are
+/// isolated, otherwise the shared one in the game folder.
 #[tauri::command]
-pub async fn list_profiles(state: State<'_, AppState>) -> Result<Vec<Profile>> {
-    tracing::info!("Listing profiles");
+pub async fn get_profile_bepinex_path(app: AppHandle, profile_id: String) -> Result<String> {
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let valheim_path = system_operations::detect_valheim_path(app.clone()).await?;
+
+    let path = if settings.isolated_profiles {
+        profile_manager::ensure_bepinex_root(&app_data_dir(&app)?, &profile_id, Path::new(&valheim_path))?
+    } else if !settings.bepinex_path.is_empty() {
+        PathBuf::from(&settings.bepinex_path)
+    } else {
+        Path::new(&valheim_path).join("BepInEx")
+    };
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Checks whether a profile can be launched: dependencies, duplicate and deprecated
+/// packages, packages missing from the repository, broken links in its plugins folder and
+/// the BepInEx version its mods need.
+#[tauri::command]
+pub async fn validate_profile(app: AppHandle, state: State<'_, AppState>, profile_id: String) -> Result<ProfileHealth> {
+    tracing::info!("Validating profile: {}", profile_id);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    if settings.repository_path.is_empty() {
+        return Err(AppError::Custom("Repository path is not configured".to_string()));
+    }
+    let valheim_path = system_operations::detect_valheim_path(app.clone()).await.ok();
+    let game_bepinex = if !settings.bepinex_path.is_empty() {
+        Some(PathBuf::from(&settings.bepinex_path))
+    } else {
+        valheim_path.as_ref().map(|path| Path::new(path).join("BepInEx"))
+    };
+
     let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
-    let mut stmt = conn.prepare("SELECT id, name, description, icon, color, active, created, last_used, play_time FROM profiles")?;
-
-    let rows = stmt.query_map([], |row| {
-        Ok(Profile {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            icon: row.get(3)?,
-            color: row.get(4)?,
-            active: row.get(5)?,
-            created: row.get(6)?,
-            last_used: row.get(7)?,
-            play_time: row.get(8)?,
-            mods: vec![], // TODO: Load mods for each profile if needed
-        })
-    })?;
+    let profile = profile_manager::load_profile(&conn, &profile_id)?;
+
+    // Isolated profiles are linked into their own plugins folder, shared ones only while active
+    let (plugins_dir, bepinex_root) = if settings.isolated_profiles {
+        let root = profile_manager::profile_bepinex_dir(&app_data_dir(&app)?, &profile_id);
+        let bepinex_root = if root.join("core").exists() { Some(root.clone()) } else { game_bepinex };
+        (Some(root.join("plugins")), bepinex_root)
+    } else {
+        let plugins_dir = if profile.active { mod_installer::plugins_dir(&settings) } else { None };
+        (plugins_dir, game_bepinex)
+    };
+
+    let target = HealthTarget {
+        repository_path: Path::new(&settings.repository_path),
+        plugins_dir: plugins_dir.as_deref(),
+        bepinex_root: bepinex_root.as_deref(),
+    };
+    profile_health::check(&conn, &profile_id, &target)
+}
 
-    let mut profiles = Vec::new();
-    for row in rows {
-        profiles.push(row?);
+/// Configs that belong to a profile: its own when profiles are isolated, otherwise the
+/// shared ones in the game folder.
+pub(crate) fn profile_config_dir(app: &AppHandle, settings: &AppSettings, profile_id: &str) -> Result<Option<PathBuf>> {
+    if settings.isolated_profiles {
+        Ok(Some(profile_manager::profile_bepinex_dir(&app_data_dir(app)?, profile_id).join("config")))
+    } else {
+        Ok(game_config_dir(settings))
     }
+}
+
+/// The `BepInEx/config` folder in the game install, which profiles share unless they are
+/// isolated.
+pub(crate) fn game_config_dir(settings: &AppSettings) -> Option<PathBuf> {
+    if !settings.bepinex_path.is_empty() {
+        Some(Path::new(&settings.bepinex_path).join("config"))
+    } else if !settings.valheim_path.is_empty() {
+        Some(Path::new(&settings.valheim_path).join("BepInEx").join("config"))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn app_data_dir(app: &AppHandle) -> Result<PathBuf> {
+    app.path().app_data_dir().map_err(|e| AppError::Custom(e.to_string()))
+}
+
+#[tauri::command]
+pub async fn list_profiles(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<ProfileDetails>> {
+    tracing::info!("Listing profiles");
+    let settings = settings_operations::load_settings(app).await.map_err(AppError::Custom)?;
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    profile_manager::load_profile_details(&conn, repository_dir(&settings), None)
+}
+
+#[tauri::command]
+pub async fn get_profile(app: AppHandle, state: State<'_, AppState>, id: String) -> Result<ProfileDetails> {
+    let settings = settings_operations::load_settings(app).await.map_err(AppError::Custom)?;
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    profile_manager::load_profile_details(&conn, repository_dir(&settings), Some(&id))?
+        .pop()
+        .ok_or(AppError::ProfileNotFound(id))
+}
 
-    Ok(profiles)
+/// The configured mod repository, if any.
+fn repository_dir(settings: &AppSettings) -> Option<&Path> {
+    (!settings.repository_path.is_empty()).then(|| Path::new(&settings.repository_path))
 }
 
 #[tauri::command]
@@ -164,44 +391,321 @@ pub async fn export_profile_to_code(state: State<'_, AppState>, profile_id: Stri
     Ok(code)
 }
 
+/// Resolves a profile code into an install plan without changing anything.
 #[tauri::command]
-pub async fn import_profile_from_code(state: State<'_, AppState>, code: String, new_name: String) -> Result<Profile> {
+pub async fn preview_profile_import(app: AppHandle, state: State<'_, AppState>, code: String) -> Result<ImportPlan> {
+    let manifest = decode_profile_code(&code)?;
+    let settings = settings_operations::load_settings(app).await.map_err(AppError::Custom)?;
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    profile_import::resolve(&conn, &import_entries(&manifest), Path::new(&settings.repository_path))
+}
+
+/// Creates a profile from a code, downloading every resolved package that is not in the
+/// repository yet. Entries that cannot be resolved are reported and left out of the profile.
+#[tauri::command]
+pub async fn import_profile_from_code(app: AppHandle, state: State<'_, AppState>, code: String, new_name: String) -> Result<ImportResult> {
     tracing::info!("Importing profile from code...");
+    let manifest = decode_profile_code(&code)?;
+    let settings = settings_operations::load_settings(app).await.map_err(AppError::Custom)?;
+    if settings.repository_path.is_empty() {
+        return Err(AppError::Custom("Repository path is not configured".to_string()));
+    }
+
+    let plan = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        profile_manager::validate_name(&conn, &new_name, None)?;
+        profile_import::resolve(&conn, &import_entries(&manifest), Path::new(&settings.repository_path))?
+    };
 
-    // Decode Base64
-    let compressed = general_purpose::STANDARD.decode(&code).map_err(|e| AppError::Custom(format!("Base64 decode failed: {}", e)))?;
+    install_plan(&state, &settings.repository_path, plan, new_name).await
+}
 
-    // Gunzip
-    let mut decoder = GzDecoder::new(&compressed[..]);
-    let mut json = String::new();
-    decoder.read_to_string(&mut json)?;
+/// Creates a client profile matching a dedicated server: every package found in the
+/// server's plugins folder is pinned to the server's version. `server_path` may be the
+/// server folder, its BepInEx or plugins folder, or a repository-style folder.
+#[tauri::command]
+pub async fn create_profile_from_server(app: AppHandle, state: State<'_, AppState>, server_path: String, new_name: String) -> Result<ServerProfileResult> {
+    tracing::info!("Creating profile from server plugins in {}", server_path);
+    let settings = settings_operations::load_settings(app).await.map_err(AppError::Custom)?;
+    if settings.repository_path.is_empty() {
+        return Err(AppError::Custom("Repository path is not configured".to_string()));
+    }
+    if !Path::new(&server_path).is_dir() {
+        return Err(AppError::Validation(format!("{} is not a folder", server_path)));
+    }
+
+    let (plan, server_only, unmatched) = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        profile_manager::validate_name(&conn, &new_name, None)?;
+        let scan = server_import::scan(&conn, Path::new(&server_path))?;
+        tracing::info!(
+            "Found {} packages and {} unknown plugins in {}",
+            scan.packages.len(), scan.unmatched.len(), scan.plugins_dir.display()
+        );
+        let (entries, server_only) = server_import::import_entries(&conn, &scan.packages)?;
+        let plan = profile_import::resolve(&conn, &entries, Path::new(&settings.repository_path))?;
+        (plan, server_only, scan.unmatched)
+    };
 
-    // Parse JSON
-    let manifest: ProfileManifest = serde_json::from_str(&json).map_err(|e| AppError::Custom(format!("JSON parse failed: {}", e)))?;
+    let import = install_plan(&state, &settings.repository_path, plan, new_name).await?;
+    Ok(ServerProfileResult { import, server_only, unmatched })
+}
 
-    // Create Profile
-    let mut new_profile = create_profile(state.clone(), new_name).await?;
+/// Downloads the missing packages of a plan, then creates the profile from the entries
+/// that ended up in the repository.
+pub(crate) async fn install_plan(state: &AppState, repository_path: &str, plan: ImportPlan, name: String) -> Result<ImportResult> {
+    let missing: Vec<(String, String)> = plan
+        .entries
+        .iter()
+        .filter(|e| !e.installed)
+        .map(|e| (e.mod_id.clone(), e.download_url.clone()))
+        .collect();
+    let downloads = stream::iter(missing)
+        .map(|(mod_id, url)| {
+            let repository_path = repository_path.to_string();
+            async move {
+                let outcome = mod_operations::install_single_mod(state, &repository_path, &mod_id, &url, None).await;
+                (mod_id, outcome)
+            }
+        })
+        .buffer_unordered(5)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut installed = Vec::new();
+    let mut failed = Vec::new();
+    for (mod_id, outcome) in downloads {
+        match outcome {
+            Ok(()) => installed.push(mod_id),
+            Err(e) => {
+                tracing::warn!("Failed to install {} for imported profile: {}", mod_id, e);
+                failed.push(format!("{}: {}", mod_id, e));
+            }
+        }
+    }
+    installed.sort();
+    failed.sort();
+
+    let profile = {
+        let mut conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let name = profile_manager::validate_name(&conn, &name, None)?;
+        let tx = conn.transaction()?;
+        let id = profile_manager::insert_profile(&tx, &name, "", "", "")?;
+        for entry in plan.entries.iter().filter(|e| e.installed || installed.contains(&e.mod_id)) {
+            tx.execute(
+                "INSERT OR REPLACE INTO profile_mods (profile_id, mod_id, enabled, version) VALUES (?1, ?2, ?3, ?4)",
+                (&id, &entry.mod_id, entry.enabled, &entry.version),
+            )?;
+        }
+        profile_history::record(&tx, &id, "Imported", &[])?;
+        tx.commit()?;
+        profile_manager::load_profile(&conn, &id)?
+    };
 
-    // Add mods to profile_mods
-    // Also trigger install? Ideally yes, but install_mod requires repository path etc.
-    // For now we just populate the database. The user might need to "Sync" or "Update All".
-    // Or we can assume we need to trigger installs.
+    Ok(ImportResult { profile, installed, failed, unresolved: plan.unresolved, unapplied_configs: vec![] })
+}
+
+/// Compares two profiles: packages and, when they have separate config folders, the
+/// values in their `.cfg` files.
+#[tauri::command]
+pub async fn diff_profiles(app: AppHandle, state: State<'_, AppState>, left_id: String, right_id: String) -> Result<ProfileDiff> {
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let (left, right) = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        profile_manager::load_profile(&conn, &left_id)?;
+        profile_manager::load_profile(&conn, &right_id)?;
+        (profile_diff::profile_packages(&conn, &left_id)?, profile_diff::profile_packages(&conn, &right_id)?)
+    };
 
-    // To properly install, we need to know repository path, which is in settings.
-    // Since we don't have settings passed here easily without reading DB or args,
-    // we will just register the mods in the profile.
-    // The frontend should probably trigger "Sync Profile" or "Install Missing Mods" after import.
+    let configs = match (
+        profile_config_dir(&app, &settings, &left_id)?,
+        profile_config_dir(&app, &settings, &right_id)?,
+    ) {
+        (Some(l), Some(r)) => profile_diff::diff_configs(&l, &r)?,
+        _ => vec![],
+    };
+    Ok(ProfileDiff { packages: profile_diff::diff_packages(&left, &right), configs })
+}
 
+/// Compares a profile with a profile code. Codes carry no configs, so only packages differ.
+#[tauri::command]
+pub async fn diff_profile_with_code(state: State<'_, AppState>, profile_id: String, code: String) -> Result<ProfileDiff> {
+    let right = profile_diff::entry_packages(&import_entries(&decode_profile_code(&code)?));
     let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    profile_manager::load_profile(&conn, &profile_id)?;
+    let left = profile_diff::profile_packages(&conn, &profile_id)?;
+    Ok(ProfileDiff { packages: profile_diff::diff_packages(&left, &right), configs: vec![] })
+}
+
+/// Applies the selected differences of `source_id` to `target_id`: each listed package
+/// takes the source's version and enabled state (or is removed), each listed config value
+/// the source's value. Returns what still differs afterwards. The packages are saved first,
+/// so later failures are reported alongside the result rather than as an error.
+#[tauri::command]
+pub async fn merge_profiles(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    source_id: String,
+    target_id: String,
+    packages: Vec<String>,
+    configs: Vec<ConfigKey>,
+) -> Result<ProfileMergeResult> {
+    tracing::info!("Merging {} packages and {} config values from {} into {}", packages.len(), configs.len(), source_id, target_id);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+
+    let target_active = {
+        let mut conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        profile_manager::load_profile(&conn, &source_id)?;
+        let target = profile_manager::load_profile(&conn, &target_id)?;
+        let source = profile_diff::profile_packages(&conn, &source_id)?;
+        profile_diff::merge_packages(&mut conn, &target_id, &source, &packages)?;
+        target.active
+    };
+
+    let mut config_error = None;
+    if !configs.is_empty() {
+        let merged = match (
+            profile_config_dir(&app, &settings, &source_id)?,
+            profile_config_dir(&app, &settings, &target_id)?,
+        ) {
+            (Some(source_dir), Some(target_dir)) => profile_diff::merge_configs(&source_dir, &target_dir, &configs),
+            _ => Ok(()),
+        };
+        if let Err(e) = merged {
+            tracing::warn!("Profile merged, but merging its configs failed: {}", e);
+            config_error = Some(e.to_string());
+        }
+    }
 
-    for mod_entry in manifest.mods {
-        // mod_entry.name is "Team-Name-Version"
-        // Insert into profile_mods
-        conn.execute(
-            "INSERT OR REPLACE INTO profile_mods (profile_id, mod_id, enabled, version) VALUES (?1, ?2, ?3, ?4)",
-            (&new_profile.id, &mod_entry.name, mod_entry.enabled, "0.0.0"), // TODO: Parse version from name
-        )?;
+    let mut relink_error = None;
+    if target_active && !packages.is_empty() {
+        if let Err(e) = switch_profile(app.clone(), state.clone(), target_id.clone()).await {
+            tracing::warn!("Profile merged, but re-linking its mods failed: {}", e);
+            relink_error = Some(e.to_string());
+        }
     }
 
-    Ok(new_profile)
-}
\ No newline at end of file
+    let remaining = diff_profiles(app, state, target_id, source_id).await?;
+    Ok(ProfileMergeResult { remaining, config_error, relink_error })
+}
+
+/// Recorded changes to a profile's mod list, newest first.
+#[tauri::command]
+pub async fn list_profile_history(state: State<'_, AppState>, profile_id: String) -> Result<Vec<ProfileHistoryEntry>> {
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    profile_manager::load_profile(&conn, &profile_id)?;
+    profile_history::list(&conn, &profile_id)
+}
+
+/// Puts a profile's mod list back to how it was right after the given history entry.
+/// Versions no longer in the repository are downloaded again; the revert itself becomes a
+/// new history entry, so it can be undone as well.
+#[tauri::command]
+pub async fn revert_profile(app: AppHandle, state: State<'_, AppState>, profile_id: String, history_id: i64) -> Result<ProfileRevertResult> {
+    tracing::info!("Reverting profile {} to history entry {}", profile_id, history_id);
+    let mods = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        profile_history::state_at(&conn, &profile_id, history_id, false)?
+    };
+    restore_history(app, state, profile_id, mods, HistoryRestore::Revert(history_id)).await
+}
+
+/// Reverses the most recent change to a profile's mod list that has not been undone yet,
+/// so calling it again keeps stepping back.
+#[tauri::command]
+pub async fn undo_profile_change(app: AppHandle, state: State<'_, AppState>, profile_id: String) -> Result<ProfileRevertResult> {
+    let (history_id, mods) = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let history_id = profile_history::latest_undoable(&conn, &profile_id)?
+            .ok_or_else(|| AppError::Validation("Profile has no changes to undo".to_string()))?;
+        (history_id, profile_history::state_at(&conn, &profile_id, history_id, true)?)
+    };
+    tracing::info!("Undoing history entry {} of profile {}", history_id, profile_id);
+    restore_history(app, state, profile_id, mods, HistoryRestore::Undo(history_id)).await
+}
+
+/// Why a profile's mod list is put back to an earlier state, with the history entry.
+enum HistoryRestore {
+    Revert(i64),
+    Undo(i64),
+}
+
+async fn restore_history(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    profile_id: String,
+    mods: Vec<profile_history::HistoryMod>,
+    reason: HistoryRestore,
+) -> Result<ProfileRevertResult> {
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    if settings.repository_path.is_empty() {
+        return Err(AppError::Custom("Repository path is not configured".to_string()));
+    }
+    let repository_path = Path::new(&settings.repository_path);
+
+    let downloads: Vec<(String, Option<String>)> = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        profile_manager::load_profile(&conn, &profile_id)?;
+        let mut stmt = conn.prepare("SELECT download_url FROM mod_versions WHERE full_name = ?1")?;
+        let mut downloads = Vec::new();
+        for m in mods.iter().filter(|m| !repository_path.join(&m.mod_id).exists()) {
+            let url: Option<String> = stmt.query_row([&m.mod_id], |row| row.get(0)).optional()?;
+            downloads.push((m.mod_id.clone(), url.filter(|url| !url.is_empty())));
+        }
+        downloads
+    };
+
+    let mut installed = Vec::new();
+    let mut failed = Vec::new();
+    for (mod_id, url) in downloads {
+        let Some(url) = url else {
+            failed.push(format!("{}: not in the catalog", mod_id));
+            continue;
+        };
+        match mod_operations::install_single_mod(&state, &settings.repository_path, &mod_id, &url, None).await {
+            Ok(()) => installed.push(mod_id),
+            Err(e) => failed.push(format!("{}: {}", mod_id, e)),
+        }
+    }
+
+    let profile = {
+        let mut conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        match reason {
+            HistoryRestore::Revert(history_id) => {
+                profile_history::restore(&mut conn, &profile_id, &mods, &format!("Reverted to #{}", history_id))?
+            }
+            HistoryRestore::Undo(history_id) => profile_history::restore_undo(&mut conn, &profile_id, &mods, history_id)?,
+        }
+        profile_manager::load_profile(&conn, &profile_id)?
+    };
+
+    let mut relink_error = None;
+    if profile.active {
+        if let Err(e) = switch_profile(app, state, profile_id).await {
+            tracing::warn!("Profile reverted, but re-linking its mods failed: {}", e);
+            relink_error = Some(e.to_string());
+        }
+    }
+
+    Ok(ProfileRevertResult { profile, installed, failed, relink_error })
+}
+
+fn decode_profile_code(code: &str) -> Result<ProfileManifest> {
+    let compressed = general_purpose::STANDARD
+        .decode(code.trim())
+        .map_err(|e| AppError::Custom(format!("Base64 decode failed: {}", e)))?;
+
+    let mut decoder = GzDecoder::new(&compressed[..]);
+    let mut json = String::new();
+    decoder.read_to_string(&mut json)?;
+
+    serde_json::from_str(&json).map_err(|e| AppError::Custom(format!("JSON parse failed: {}", e)))
+}
+
+fn import_entries(manifest: &ProfileManifest) -> Vec<ImportEntry> {
+    manifest
+        .mods
+        .iter()
+        .map(|m| ImportEntry { name: m.name.clone(), enabled: m.enabled })
+        .collect()
+}
@@ -0,0 +1,227 @@
+use crate::commands::{mod_operations, profile_operations, settings_operations};
+use crate::error::{AppError, Result};
+use crate::models::{ExternalProfile, ImportResult, MigratedProfile};
+use crate::services::profile_import::{self, ImportEntry};
+use crate::services::profile_manager;
+use crate::services::r2modman::{self, R2Export, R2Mod, R2Version};
+use crate::state::AppState;
+use crate::utils::{file_ops, version};
+use rusqlite::Connection;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+/// Imports an r2modman `.r2z` export. Bundled configs are stored in the new profile's own
+/// BepInEx folder, so they apply when isolated profiles are enabled and never overwrite
+/// the shared configs in the game folder.
+#[tauri::command]
+pub async fn import_r2z(app: AppHandle, state: State<'_, AppState>, path: String, new_name: Option<String>) -> Result<ImportResult> {
+    tracing::info!("Importing r2modman profile from {}", path);
+    let data = fs::read(&path)?;
+    import_archive(&app, &state, &data, new_name).await
+}
+
+/// Imports a profile shared as an r2modman profile code.
+#[tauri::command]
+pub async fn import_r2modman_code(app: AppHandle, state: State<'_, AppState>, code: String, new_name: Option<String>) -> Result<ImportResult> {
+    tracing::info!("Importing r2modman profile code {}", code.trim());
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let data = r2modman::fetch_code(&settings.profile_code_endpoint, &code).await?;
+    import_archive(&app, &state, &data, new_name).await
+}
+
+#[tauri::command]
+pub async fn export_r2z(app: AppHandle, state: State<'_, AppState>, profile_id: String, path: String) -> Result<()> {
+    tracing::info!("Exporting profile {} to {}", profile_id, path);
+    let data = build_archive(&app, &state, &profile_id).await?;
+    fs::write(&path, data)?;
+    Ok(())
+}
+
+/// Uploads the profile as an `.r2z` archive and returns a code r2modman can import.
+#[tauri::command]
+pub async fn export_r2modman_code(app: AppHandle, state: State<'_, AppState>, profile_id: String) -> Result<String> {
+    tracing::info!("Exporting profile {} as r2modman profile code", profile_id);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let data = build_archive(&app, &state, &profile_id).await?;
+    r2modman::upload_code(&settings.profile_code_endpoint, &data).await
+}
+
+/// Lists the profiles of r2modman / Thunderstore Mod Manager installations, either in
+/// `data_dir` (a game folder such as `.../r2modmanPlus-local/Valheim`) or the default locations.
+#[tauri::command]
+pub async fn detect_r2modman_profiles(data_dir: Option<String>) -> Result<Vec<ExternalProfile>> {
+    let mut profiles = Vec::new();
+    for dir in source_dirs(data_dir) {
+        for profile in r2modman::read_profiles(&dir)? {
+            profiles.push(ExternalProfile {
+                name: profile.name,
+                path: profile.dir.to_string_lossy().to_string(),
+                mod_count: profile.mods.len(),
+            });
+        }
+    }
+    Ok(profiles)
+}
+
+/// Re-creates r2modman profiles as Deftheim profiles. Packages are taken from the
+/// repository or r2modman's download cache where possible and only downloaded otherwise;
+/// each profile's configs are copied into its own BepInEx folder. `profiles` limits the
+/// migration to the given profile names.
+#[tauri::command]
+pub async fn migrate_r2modman(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    data_dir: Option<String>,
+    profiles: Option<Vec<String>>,
+) -> Result<Vec<MigratedProfile>> {
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    if settings.repository_path.is_empty() {
+        return Err(AppError::Custom("Repository path is not configured".to_string()));
+    }
+    let repository_path = Path::new(&settings.repository_path);
+    let app_data = profile_operations::app_data_dir(&app)?;
+
+    let mut migrated = Vec::new();
+    for dir in source_dirs(data_dir) {
+        for source in r2modman::read_profiles(&dir)? {
+            if profiles.as_ref().is_some_and(|names| !names.contains(&source.name)) {
+                continue;
+            }
+            tracing::info!("Migrating r2modman profile {} from {}", source.name, source.dir.display());
+
+            let mut reused = Vec::new();
+            for managed in &source.mods {
+                let mod_id = managed.mod_id();
+                let target = repository_path.join(&mod_id);
+                if target.exists() {
+                    continue;
+                }
+                let copy_from = match r2modman::cached_package(&dir, managed) {
+                    Some(cached) => Some(cached),
+                    // Without a cached copy, the catalog download is preferred over the
+                    // plugin folder, which lacks files r2modman moved elsewhere
+                    None if !in_catalog(&state, &mod_id)? => {
+                        Some(source.dir.join("BepInEx").join("plugins").join(&managed.name)).filter(|p| p.is_dir())
+                    }
+                    None => None,
+                };
+                if let Some(from) = copy_from {
+                    file_ops::copy_dir_all(&from, &target)?;
+                    mod_operations::update_db_from_manifest(&state, &target, &mod_id, "")?;
+                    reused.push(mod_id);
+                }
+            }
+
+            let entries: Vec<ImportEntry> = source
+                .mods
+                .iter()
+                .map(|m| ImportEntry { name: m.mod_id(), enabled: m.enabled })
+                .collect();
+            let (name, plan) = {
+                let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+                let name = available_name(&conn, &source.name)?;
+                (name, profile_import::resolve(&conn, &entries, repository_path)?)
+            };
+
+            let import = profile_operations::install_plan(&state, &settings.repository_path, plan, name).await?;
+            profile_manager::copy_config(
+                &source.dir.join("BepInEx"),
+                &profile_manager::profile_bepinex_dir(&app_data, &import.profile.id),
+            )?;
+            migrated.push(MigratedProfile { source_name: source.name, reused, import });
+        }
+    }
+    Ok(migrated)
+}
+
+fn source_dirs(data_dir: Option<String>) -> Vec<PathBuf> {
+    match data_dir {
+        Some(dir) => vec![PathBuf::from(dir)],
+        None => r2modman::data_dirs(),
+    }
+}
+
+fn in_catalog(state: &AppState, mod_id: &str) -> Result<bool> {
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    Ok(conn.query_row("SELECT EXISTS(SELECT 1 FROM mod_versions WHERE full_name = ?1)", [mod_id], |row| row.get(0))?)
+}
+
+/// `name`, or `name (r2modman)`, `name (r2modman 2)`, ... if a profile already uses it.
+fn available_name(conn: &Connection, name: &str) -> Result<String> {
+    let suffixed = (1..100).map(|n| match n {
+        1 => format!("{} (r2modman)", name),
+        n => format!("{} (r2modman {})", name, n),
+    });
+    for candidate in std::iter::once(name.to_string()).chain(suffixed) {
+        match profile_manager::validate_name(conn, &candidate, None) {
+            Err(AppError::Validation(_)) => continue,
+            outcome => return outcome,
+        }
+    }
+    Err(AppError::Validation(format!("No free profile name for '{}'", name)))
+}
+
+async fn import_archive(app: &AppHandle, state: &AppState, data: &[u8], new_name: Option<String>) -> Result<ImportResult> {
+    let archive = r2modman::read_r2z(data)?;
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    if settings.repository_path.is_empty() {
+        return Err(AppError::Custom("Repository path is not configured".to_string()));
+    }
+
+    let name = new_name.unwrap_or_else(|| archive.export.profile_name.clone());
+    let entries: Vec<ImportEntry> = archive
+        .export
+        .mods
+        .iter()
+        .map(|m| ImportEntry { name: m.mod_id(), enabled: m.enabled })
+        .collect();
+    let plan = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        profile_manager::validate_name(&conn, &name, None)?;
+        profile_import::resolve(&conn, &entries, Path::new(&settings.repository_path))?
+    };
+
+    let mut result = profile_operations::install_plan(state, &settings.repository_path, plan, name).await?;
+    if !archive.configs.is_empty() {
+        let app_data = profile_operations::app_data_dir(app)?;
+        let config_dir = profile_manager::profile_bepinex_dir(&app_data, &result.profile.id).join("config");
+        file_ops::write_files(&config_dir, &archive.configs)?;
+        if !settings.isolated_profiles {
+            result.unapplied_configs = archive
+                .configs
+                .iter()
+                .map(|(relative, _)| relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+                .collect();
+            tracing::warn!(
+                "Profiles are not isolated, {} configs from the archive will not be loaded by the game",
+                result.unapplied_configs.len()
+            );
+        }
+    }
+    Ok(result)
+}
+
+async fn build_archive(app: &AppHandle, state: &AppState, profile_id: &str) -> Result<Vec<u8>> {
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let export = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let profile = profile_manager::load_profile(&conn, profile_id)?;
+        let mut stmt = conn.prepare("SELECT mod_id, enabled FROM profile_mods WHERE profile_id = ?1 ORDER BY mod_id")?;
+        let rows: Vec<(String, bool)> = stmt
+            .query_map([profile_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut mods = Vec::new();
+        for (mod_id, enabled) in rows {
+            match version::split_package_id(&mod_id).and_then(|(name, v)| Some((name, R2Version::parse(v)?))) {
+                Some((name, version)) => mods.push(R2Mod { name: name.to_string(), version, enabled }),
+                None => tracing::warn!("Skipping {} in r2modman export, it has no version", mod_id),
+            }
+        }
+        R2Export { profile_name: profile.name, mods }
+    };
+
+    let config_dir = profile_operations::profile_config_dir(app, &settings, profile_id)?;
+    r2modman::write_r2z(&export, config_dir.as_deref())
+}
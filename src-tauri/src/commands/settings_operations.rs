@@ -37,17 +37,7 @@ pub async fn load_settings(app: AppHandle) -> Result<AppSettings, String> {
 
     if !path.exists() {
         tracing::info!("Settings file not found, returning defaults");
-        // Return default settings
-        return Ok(AppSettings {
-            valheim_path: "".to_string(),
-            bepinex_path: "".to_string(),
-            repository_path: "".to_string(),
-            backup_path: "".to_string(),
-            theme: "dark".to_string(),
-            auto_update: true,
-            auto_backup: true,
-            language: "en".to_string(),
-        });
+        return Ok(AppSettings::default());
     }
 
     let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
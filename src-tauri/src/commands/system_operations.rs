@@ -1,15 +1,20 @@
 use crate::error::{AppError, Result};
-use crate::commands::settings_operations;
+use crate::commands::{profile_operations, settings_operations};
+use crate::models::PlaySession;
+use crate::services::backup_scheduler::{self, Trigger};
+use crate::services::{game_launcher, profile_manager, session_tracker};
+use crate::state::AppState;
+use rusqlite::OptionalExtension;
 use std::path::{Path, PathBuf};
 use std::fs;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager, State};
 
 #[cfg(target_os = "windows")]
 use winreg::enums::*;
 #[cfg(target_os = "windows")]
 use winreg::RegKey;
 
-const VALHEIM_APP_ID: u32 = 892970;
+pub(crate) const VALHEIM_APP_ID: u32 = 892970;
 
 fn get_steam_path() -> Option<PathBuf> {
     #[cfg(target_os = "windows")]
@@ -197,26 +202,76 @@ pub async fn launch_valheim(app: AppHandle, profile_id: Option<String>) -> Resul
         return Err(AppError::ValheimNotFound);
     }
 
-    // 2. If profile is selected, activate it (using switch_profile logic)
-    // We can't easily call `switch_profile` directly if it's an async command requiring state that we might not have easy access to here without `State`.
-    // However, `switch_profile` logic should be accessible.
-    // For now, let's assume the user has already switched profile in the UI, or we enforce it here if we refactor `switch_profile` to be a service method.
-    // Given the constraints, let's setup the environment variables manually here if needed.
+    // 2. If a profile is selected, make the plugins folder reflect it before launching.
+    // switch_profile only touches links that differ, so this is cheap when already active.
+    // Isolated profiles always need one, so fall back to the active profile.
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let switch_to = match profile_id {
+        Some(id) => Some(id),
+        None if settings.isolated_profiles => active_profile_id(&app)?,
+        None => None,
+    };
+    if let Some(id) = &switch_to {
+        profile_operations::switch_profile(app.clone(), app.state::<AppState>(), id.clone()).await?;
+    }
 
-    // START_UP_ENV setup for Doorstop/BepInEx
-    // If BepInEx is installed, we might need to set specific env vars to point to the profile's BepInEx config/plugins?
-    // Standard BepInEx usually loads from the game folder.
-    // If we are using symlinks (enable_mod), the "active" profile's mods are already in the game folder.
-    // So simply launching the game is sufficient IF `enable_mod` was called for all mods in the active profile.
-    // The `switch_profile` command (in profile_operations) is responsible for clearing the game folder and symlinking the new profile's mods.
-    // So we assume the UI calls `switch_profile` -> `launch_valheim`.
+    // 3. Back up profiles, configs and saves as they are before playing, if enabled. This has
+    // to finish before the game starts: BepInEx rewrites config files while loading plugins.
+    backup_scheduler::run(&app, Trigger::Launch).await;
 
     // Ideally, we launch via Steam to ensure overlay works, but direct launch is requested/supported.
     // On Linux/macOS, we might need to set LD_LIBRARY_PATH or similar if not launching via Steam.
 
-    // Launch
+    // Launch and keep the process, so the session and play time can be tracked
     tracing::info!("Executing: {:?}", exe_path);
-    open::that(exe_path).map_err(AppError::Io)?;
+    let child = match switch_to.as_ref().filter(|_| settings.isolated_profiles) {
+        Some(id) => {
+            let bepinex_root = profile_manager::profile_bepinex_dir(&profile_operations::app_data_dir(&app)?, id);
+            tracing::info!("Using isolated BepInEx root: {:?}", bepinex_root);
+            game_launcher::isolated_command(&valheim_path, &exe_path, &bepinex_root).spawn()?
+        }
+        None => game_launcher::command(&valheim_path, &exe_path).spawn()?,
+    };
+    // Play time goes to the profile the game runs with: the one switched to, or else the
+    // active one, whose mods the shared plugins folder holds
+    let session_profile = match switch_to {
+        Some(id) => Some(id),
+        None => active_profile_id(&app)?,
+    };
+    session_tracker::track(app, session_profile, child)?;
 
     Ok(())
 }
+
+/// Play sessions, newest first, optionally only those of one profile.
+#[tauri::command]
+pub async fn list_play_sessions(state: State<'_, AppState>, profile_id: Option<String>, limit: Option<u32>) -> Result<Vec<PlaySession>> {
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    let mut stmt = conn.prepare(
+        "SELECT id, profile_id, started_at, ended_at, exit_code, duration_seconds FROM play_sessions
+         WHERE ?1 IS NULL OR profile_id = ?1
+         ORDER BY started_at DESC
+         LIMIT ?2",
+    )?;
+    let sessions = stmt
+        .query_map((&profile_id, limit.map_or(-1, i64::from)), |row| {
+            Ok(PlaySession {
+                id: row.get(0)?,
+                profile_id: row.get(1)?,
+                started_at: row.get(2)?,
+                ended_at: row.get(3)?,
+                exit_code: row.get(4)?,
+                duration_seconds: row.get(5)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(sessions)
+}
+
+fn active_profile_id(app: &AppHandle) -> Result<Option<String>> {
+    let state = app.state::<AppState>();
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    Ok(conn
+        .query_row("SELECT id FROM profiles WHERE active = 1", [], |row| row.get(0))
+        .optional()?)
+}
@@ -1,207 +1,597 @@
+use crate::commands::{mod_operations, profile_operations, settings_operations};
 use crate::error::{Result, AppError};
+use crate::models::{
+    AppSettings, DependentImpact, HeldUpdate, ProfileRef, RequiredDependency, UpdateHistoryEntry, UpdateImpact, UpdateInfo, UpdatePolicy,
+    UpdatePolicyRule, UpdateReport,
+};
+use crate::services::{mod_installer, profile_history, profile_manager};
+use crate::services::update_checker::{self, UpdateChecker};
 use crate::state::AppState;
-use tauri::State;
-use serde::{Deserialize, Serialize};
+use crate::utils::version;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateInfo {
-    pub mod_id: String,
-    pub current_version: String,
-    pub latest_version: String,
-    pub changelog: String,
-    pub download_url: String,
+#[tauri::command]
+pub async fn check_updates(state: State<'_, AppState>) -> Result<UpdateReport> {
+    tracing::info!("Checking for updates...");
+
+    // Installed packages are stored in 'mods' under their versioned id ("Team-Name-1.0.0"),
+    // while catalog entries from Thunderstore use the bare package name ("Team-Name").
+    // Only the former can have updates.
+    let mut report = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let mut stmt = conn.prepare("SELECT id FROM mods")?;
+        let installed_ids: Vec<String> = stmt.query_map([], |row| row.get(0))?.filter_map(|r| r.ok()).collect();
+        collect_updates(&conn, installed_ids, None)?
+    };
+
+    attach_changelogs(&state, &mut report).await;
+    Ok(report)
 }
 
+/// Checks only the packages referenced by one profile, applying that profile's update policies.
 #[tauri::command]
-pub async fn check_updates(state: State<'_, AppState>) -> Result<Vec<UpdateInfo>> {
-    tracing::info!("Checking for updates...");
+pub async fn check_profile_updates(state: State<'_, AppState>, profile_id: String) -> Result<UpdateReport> {
+    tracing::info!("Checking for updates in profile: {}", profile_id);
 
-    // We compare installed mods (from repository scan or DB?) with latest versions in DB (mod_versions).
-    // Assuming DB has "latest" info from Thunderstore fetch.
+    let mut report = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let installed_ids = profile_mod_ids(&conn, &profile_id)?;
+        collect_updates(&conn, installed_ids, Some(&profile_id))?
+    };
 
-    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    attach_changelogs(&state, &mut report).await;
+    Ok(report)
+}
 
-    // Query mods that have newer versions
-    // We assume 'mods' table has the installed version or we check 'profile_mods' vs 'mod_versions'.
-    // Actually, 'mods' table in current schema seems to store metadata but not necessarily "installed version" per se,
-    // though 'scan_mods' populates 'mods' table.
-    // However, 'scan_mods' populates 'mods' table with info from manifest.
+fn profile_mod_ids(conn: &Connection, profile_id: &str) -> Result<Vec<String>> {
+    let exists: bool = conn.query_row("SELECT EXISTS(SELECT 1 FROM profiles WHERE id = ?1)", [profile_id], |row| row.get(0))?;
+    if !exists {
+        return Err(AppError::ProfileNotFound(profile_id.to_string()));
+    }
 
-    // Let's assume 'mods' table has installed info.
-    // And 'mod_versions' table has ALL versions including latest (fetched by ThunderstoreService).
-    // Wait, 'ThunderstoreService' populates 'mod_versions'.
+    let mut stmt = conn.prepare("SELECT mod_id FROM profile_mods WHERE profile_id = ?1")?;
+    let ids = stmt.query_map([profile_id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+    Ok(ids)
+}
 
-    // Better strategy:
-    // 1. Get list of installed mods from 'mods' table (where installed=true if we tracked it, or just all in 'mods').
-    // 2. For each installed mod, find latest version in 'mod_versions' (which comes from API).
-    // 3. Compare version strings.
+/// Compares each installed package id against every known version in 'mod_versions'.
+/// `profile_id` narrows policy resolution to that profile's rules.
+fn collect_updates(conn: &Connection, installed_ids: Vec<String>, profile_id: Option<&str>) -> Result<UpdateReport> {
+    let mut ver_stmt = conn.prepare(
+        "SELECT full_name, version_number, download_url FROM mod_versions WHERE full_name LIKE ?1"
+    )?;
 
-    // BUT 'mods' table has 'version' column? No, 'ModInfo' has 'version'.
-    // 'mods' table schema: id, name, owner, full_name, package_url...
-    // It doesn't seem to have 'version' column explicitly in the CREATE TABLE statement in schema.rs?
-    // Let's check schema.rs again.
-    // "CREATE TABLE IF NOT EXISTS mods (id TEXT PRIMARY KEY, name TEXT... full_name TEXT...)"
-    // It does not have 'current_version'.
+    let mut report = UpdateReport::default();
+    for installed_id in installed_ids {
+        let Some((package_name, installed_version)) = version::split_package_id(&installed_id) else {
+            continue;
+        };
 
-    // 'mod_versions' table has 'version_number'.
+        // LIKE also matches longer package names sharing the prefix, so filter exactly
+        let pattern = format!("{}-%", package_name);
+        let candidates: Vec<(String, String)> = ver_stmt
+            .query_map([&pattern], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+            .filter_map(|r| r.ok())
+            .filter(|(full_name, _, _)| version::split_package_id(full_name).is_some_and(|(p, _)| p == package_name))
+            .filter(|(_, ver, _)| version::is_newer(ver, installed_version))
+            .map(|(_, ver, url)| (ver, url))
+            .collect();
+
+        let Some(newest) = candidates.iter().map(|(ver, _)| ver).max_by_key(|ver| version::parse(ver)).cloned() else {
+            continue;
+        };
 
-    // If 'scan_mods' was called, it populates 'mods' table.
-    // But 'scan_mods' logic in 'mod_operations.rs':
-    // "INSERT OR IGNORE INTO mods ... VALUES (mod_id, manifest.name...)"
-    // And "INSERT OR IGNORE INTO mod_versions ..."
+        let policy = update_checker::effective_policy(conn, package_name, profile_id)?;
+        let allowed = candidates
+            .into_iter()
+            .filter(|(ver, _)| update_checker::policy_allows(policy, installed_version, ver))
+            .max_by_key(|(ver, _)| version::parse(ver));
+
+        if allowed.as_ref().is_none_or(|(ver, _)| *ver != newest) {
+            report.held.push(HeldUpdate {
+                mod_id: installed_id.clone(),
+                current_version: installed_version.to_string(),
+                latest_version: newest,
+                policy,
+            });
+        }
 
-    // This is tricky. The DB stores both installed and available mods in the same tables?
-    // If so, how do we distinguish?
-    // 'ThunderstoreService' populates 'mods' and 'mod_versions' from API.
-    // 'scan_mods' populates them from disk.
-    // Use 'mod_versions' table to find "latest" (order by date_created desc or semver).
+        if let Some((latest_version, download_url)) = allowed {
+            report.updates.push(UpdateInfo {
+                mod_id: installed_id.clone(),
+                current_version: installed_version.to_string(),
+                latest_version,
+                changelog: String::new(),
+                download_url,
+            });
+        }
+    }
+    Ok(report)
+}
 
-    // Simplification: We iterate over directory names in repository (via scan_mods logic or assuming we know them)
-    // and compare with DB.
+/// Changelogs may need a network round-trip, so they are resolved after the DB lock is released.
+/// A missing changelog should never hide an available update.
+async fn attach_changelogs(state: &AppState, report: &mut UpdateReport) {
+    let checker = UpdateChecker::new(state.db.clone());
+    for update in &mut report.updates {
+        let Some((package_name, _)) = version::split_package_id(&update.mod_id) else {
+            continue;
+        };
+        match checker.changelog_between(package_name, &update.current_version, &update.latest_version).await {
+            Ok(changelog) => update.changelog = changelog,
+            Err(e) => tracing::warn!("Could not load changelog for {}: {}", update.mod_id, e),
+        }
+    }
+}
 
-    // Let's query 'mods' table for installed mods.
-    // We need a way to know what is currently installed.
-    // 'scan_mods' returns a list.
-    // Let's trust the 'mods' table contains what we know about.
+/// Lists, for every proposed update, the profiles it touches, the installed packages that
+/// depend on it and the dependencies it needs newer versions of. Scoped to one profile
+/// when `profile_id` is given.
+#[tauri::command]
+pub async fn analyze_update_impact(state: State<'_, AppState>, profile_id: Option<String>) -> Result<Vec<UpdateImpact>> {
+    tracing::info!("Analyzing update impact for profile: {:?}", profile_id);
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
 
-    // Actually, let's just use what 'scan_mods' would find.
-    // But 'check_updates' shouldn't necessarily re-scan disk.
+    let installed_ids = match &profile_id {
+        Some(id) => profile_mod_ids(&conn, id)?,
+        None => conn
+            .prepare("SELECT id FROM mods")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?,
+    };
+    let report = collect_updates(&conn, installed_ids.clone(), profile_id.as_deref())?;
+
+    let installed: HashMap<&str, &str> = installed_ids.iter().filter_map(|id| version::split_package_id(id)).collect();
+
+    let mut profiles_stmt = conn.prepare(
+        "SELECT p.id, p.name, p.active FROM profiles p
+         JOIN profile_mods pm ON pm.profile_id = p.id
+         WHERE pm.mod_id = ?1 ORDER BY p.name"
+    )?;
+    let mut dependents_stmt = conn.prepare(
+        "SELECT version_full_name, dependency_id FROM mod_dependencies
+         WHERE substr(dependency_id, 1, length(?1) + 1) = ?1 || '-'"
+    )?;
+    let mut deps_stmt = conn.prepare("SELECT dependency_id FROM mod_dependencies WHERE version_full_name = ?1")?;
+
+    let mut impacts = Vec::new();
+    for update in &report.updates {
+        let Some((package_name, _)) = version::split_package_id(&update.mod_id) else {
+            continue;
+        };
 
-    // Let's assume the client calls 'scan_mods' first, which updates the DB with installed versions?
-    // No, 'scan_mods' inserts with ON CONFLICT IGNORE. So if API loaded it first, installed version might be ignored if ID conflicts.
-    // IDs are 'Team-Name-Version'. So every version is unique in 'mod_versions'.
-    // But 'mods' table uses 'id' (which is full name?) as PK.
+        let profiles = profiles_stmt
+            .query_map([&update.mod_id], |row| Ok(ProfileRef { id: row.get(0)?, name: row.get(1)?, active: row.get(2)? }))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let dependents = dependents_stmt
+            .query_map([package_name], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .filter_map(|r| r.ok())
+            .filter(|(dependent, _)| installed_ids.contains(dependent))
+            .filter_map(|(dependent, dependency)| {
+                let (dep_package, requires_version) = version::split_package_id(&dependency)?;
+                (dep_package == package_name).then(|| DependentImpact {
+                    update_available: report.updates.iter().find(|u| u.mod_id == dependent).map(|u| u.latest_version.clone()),
+                    requires_version: requires_version.to_string(),
+                    mod_id: dependent,
+                })
+            })
+            .collect();
+
+        // Missing dependencies are installed by install_mod itself; only outdated ones need attention
+        let new_id = format!("{}-{}", package_name, update.latest_version);
+        let required_dependencies = deps_stmt
+            .query_map([&new_id], |row| row.get::<_, String>(0))?
+            .filter_map(|r| r.ok())
+            .filter_map(|dependency| {
+                let (dep_package, required) = version::split_package_id(&dependency)?;
+                let installed_version = installed.get(dep_package)?;
+                version::is_newer(required, installed_version).then(|| RequiredDependency {
+                    package_name: dep_package.to_string(),
+                    installed_version: installed_version.to_string(),
+                    required_version: required.to_string(),
+                })
+            })
+            .collect();
+
+        impacts.push(UpdateImpact {
+            mod_id: update.mod_id.clone(),
+            current_version: update.current_version.clone(),
+            latest_version: update.latest_version.clone(),
+            profiles,
+            dependents,
+            required_dependencies,
+        });
+    }
 
-    // If ID is 'Team-Name-Version', then an update is a DIFFERENT mod ID.
-    // e.g. 'Team-Name-1.0.0' vs 'Team-Name-1.0.1'.
+    Ok(impacts)
+}
 
-    // If so, we need to group by 'package_name' (Team-Name).
-    // But schema doesn't have 'package_name'. It has 'name' (e.g. "Jotunn").
+/// Updates one installed package. With `profile_id`, the update is checked against that
+/// profile's policies and only that profile is moved to the new version.
+#[tauri::command]
+pub async fn update_mod(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    repository_path: String,
+    mod_id: String,
+    profile_id: Option<String>,
+) -> Result<()> {
+    tracing::info!("Updating mod: {} (profile: {:?})", mod_id, profile_id);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
 
-    // Logic:
-    // 1. Get all installed mods (iterate dirs or DB).
-    // 2. Extract "Team-Name" prefix.
-    // 3. Find latest version for that "Team-Name" in 'mod_versions' or 'thunderstore' cache.
-    // 4. Compare.
+    // mod_id is the INSTALLED one (e.g. "Team-Name-1.0.0")
+    let report = match &profile_id {
+        Some(id) => check_profile_updates(state.clone(), id.clone()).await?,
+        None => check_updates(state.clone()).await?,
+    };
+    if let Some(update) = report.updates.iter().find(|u| u.mod_id == mod_id) {
+        apply_update(&app, &state, &settings, &repository_path, update, profile_id.as_deref()).await?;
+    } else if let Some(held) = report.held.iter().find(|h| h.mod_id == mod_id) {
+        return Err(AppError::Custom(format!(
+            "{} is held at {} by its '{}' update policy",
+            mod_id, held.current_version, held.policy.as_str()
+        )));
+    } else {
+        let installed: bool = {
+            let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+            conn.query_row("SELECT EXISTS(SELECT 1 FROM mods WHERE id = ?1)", [&mod_id], |row| row.get(0))?
+        };
+        return Err(if installed {
+            AppError::Custom(format!("No update available for {}", mod_id))
+        } else {
+            AppError::ModNotFound(mod_id)
+        });
+    }
 
-    // Let's use a simpler approach for now.
-    // We assume 'mods' table has 'id' = "Team-Name-Version".
-    // We split it to get "Team-Name".
-    // We query DB for max version of "Team-Name".
+    prune_retained_versions(&state, &repository_path, settings.update_retention_days)
+}
 
-    let mut stmt = conn.prepare("SELECT id FROM mods")?;
-    let installed_ids: Vec<String> = stmt.query_map([], |row| row.get(0))?.filter_map(|r| r.ok()).collect();
+#[tauri::command]
+pub async fn update_all_mods(app: AppHandle, state: State<'_, AppState>, repository_path: String) -> Result<()> {
+    tracing::info!("Updating all mods...");
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+    let report = check_updates(state.clone()).await?;
 
-    let mut updates = Vec::new();
+    for update in &report.updates {
+        apply_update(&app, &state, &settings, &repository_path, update, None).await?;
+    }
 
-    for installed_id in installed_ids {
-        // installed_id e.g. "TeamL2-Jotunn-2.15.0" or "Team-Name-With-Hyphens-1.0.0"
-        // We split by the last hyphen to separate version from package name
-        let (package_name, installed_version) = match installed_id.rsplit_once('-') {
-            Some((name, ver)) => (name, ver),
-            None => continue,
-        };
+    prune_retained_versions(&state, &repository_path, settings.update_retention_days)
+}
 
-        // Find latest version for this package in mod_versions
-        // usage of "LIKE" to find other versions of the same package
-        let mut ver_stmt = conn.prepare(
-            "SELECT version_number, download_url FROM mod_versions
-             WHERE full_name LIKE ?1
-             ORDER BY date_created DESC LIMIT 1"
-        )?;
+/// Installs the new version of a package, moves the profiles using the old version over
+/// to it and records the update. A profile is only moved when its own update policy allows
+/// the new version (and, given `profile_id`, only that profile). The old version stays
+/// installed while any profile still uses it, and is otherwise kept for rollback unless
+/// retention is disabled.
+async fn apply_update(
+    app: &AppHandle,
+    state: &State<'_, AppState>,
+    settings: &AppSettings,
+    repository_path: &str,
+    update: &UpdateInfo,
+    profile_id: Option<&str>,
+) -> Result<()> {
+    let Some((package_name, _)) = version::split_package_id(&update.mod_id) else {
+        return Err(AppError::Custom("Invalid mod ID format".to_string()));
+    };
+    let new_full_id = format!("{}-{}", package_name, update.latest_version);
+
+    mod_operations::install_mod(
+        state.clone(),
+        repository_path.to_string(),
+        new_full_id.clone(),
+        update.download_url.clone()
+    ).await?;
+
+    if update.mod_id == new_full_id {
+        return Ok(());
+    }
 
-        // Assuming IDs are consistently named "PackageName-Version"
-        // We look for anything starting with "PackageName-"
-        let pattern = format!("{}-%", package_name);
-        let mut rows = ver_stmt.query([&pattern])?;
-
-        if let Some(row) = rows.next()? {
-            let latest_version: String = row.get(0)?;
-            let download_url: String = row.get(1)?;
-
-            if latest_version != installed_version {
-                 // Simple string comparison might fail for semver (1.10 < 1.9), but good enough for now
-                 // or use a semver crate.
-                 // Let's assume inequality means update available.
-
-                 updates.push(UpdateInfo {
-                     mod_id: installed_id.clone(), // The installed ID
-                     current_version: installed_version.to_string(),
-                     latest_version: latest_version,
-                     changelog: "".to_string(), // Fetch if possible
-                     download_url,
-                 });
+    let (profile_ids, still_used) = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let using: Vec<String> = conn
+            .prepare("SELECT profile_id FROM profile_mods WHERE mod_id = ?1")?
+            .query_map([&update.mod_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut moved = Vec::new();
+        for id in using {
+            let policy = update_checker::effective_policy(&conn, package_name, Some(&id))?;
+            if profile_id.is_none_or(|p| p == id)
+                && update_checker::policy_allows(policy, &update.current_version, &update.latest_version)
+            {
+                moved.push(id);
             }
         }
+        let still_used: bool = conn.query_row(
+            "SELECT COUNT(*) FROM profile_mods WHERE mod_id = ?1",
+            [&update.mod_id],
+            |row| row.get::<_, usize>(0),
+        )? > moved.len();
+        (moved, still_used)
+    };
+
+    // A version other profiles still use stays where it is, which also keeps it available for rollback
+    let retained = still_used
+        || (settings.update_retention_days > 0 && mod_installer::retain_version(Path::new(repository_path), &update.mod_id)?);
+    if !retained {
+        mod_operations::uninstall_mod(repository_path.to_string(), update.mod_id.clone()).await?;
     }
 
-    Ok(updates)
+    let relink = {
+        let mut conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let tx = conn.transaction()?;
+
+        let action = format!("Updated {} to {}", package_name, update.latest_version);
+        for id in &profile_ids {
+            let before = profile_history::snapshot(&tx, id)?;
+            tx.execute(
+                "UPDATE OR REPLACE profile_mods SET mod_id = ?1, version = ?2 WHERE profile_id = ?3 AND mod_id = ?4",
+                (&new_full_id, &update.latest_version, id, &update.mod_id),
+            )?;
+            profile_history::record(&tx, id, &action, &before)?;
+        }
+        if !still_used {
+            mod_operations::remove_installed_from_db(&tx, &update.mod_id)?;
+        }
+        tx.execute(
+            "INSERT INTO update_history (package_name, from_version, to_version, updated_at, profile_ids, retained)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (
+                package_name,
+                &update.current_version,
+                &update.latest_version,
+                chrono::Utc::now().to_rfc3339(),
+                serde_json::to_string(&profile_ids)?,
+                retained,
+            ),
+        )?;
+
+        let relink = links_follow(&tx, settings, &profile_ids)?;
+        tx.commit()?;
+        relink
+    };
+
+    if relink {
+        relink_plugin(&plugin_dirs(app, settings, &profile_ids)?, repository_path, &update.mod_id, &new_full_id).await?;
+    }
+    Ok(())
 }
 
-#[tauri::command]
-pub async fn update_mod(state: State<'_, AppState>, repository_path: String, mod_id: String) -> Result<()> {
-    tracing::info!("Updating mod: {}", mod_id);
+/// With a shared plugins folder, links only need swapping if the active profile is among
+/// `profile_ids`; isolated profiles each have their own folder.
+fn links_follow(conn: &Connection, settings: &AppSettings, profile_ids: &[String]) -> Result<bool> {
+    if settings.isolated_profiles {
+        return Ok(true);
+    }
+    let active: Option<String> = conn
+        .query_row("SELECT id FROM profiles WHERE active = 1", [], |row| row.get(0))
+        .optional()?;
+    Ok(active.is_none_or(|id| profile_ids.contains(&id)))
+}
 
-    // 1. Check for update (get target URL and ID)
-    // mod_id is the INSTALLED one (e.g. "Team-Name-1.0.0")
+/// Plugins folders that may hold links for `profile_ids`: each profile's own folder when
+/// profiles are isolated, otherwise the shared one.
+fn plugin_dirs(app: &AppHandle, settings: &AppSettings, profile_ids: &[String]) -> Result<Vec<PathBuf>> {
+    if settings.isolated_profiles {
+        let app_data_dir = profile_operations::app_data_dir(app)?;
+        Ok(profile_ids
+            .iter()
+            .map(|id| profile_manager::profile_bepinex_dir(&app_data_dir, id).join("plugins"))
+            .collect())
+    } else {
+        Ok(mod_installer::plugins_dir(settings).into_iter().collect())
+    }
+}
 
-    let updates = check_updates(state.clone()).await?;
-    if let Some(update) = updates.iter().find(|u| u.mod_id == mod_id) {
-        // Reconstruct full ID
-        // Assuming package name is everything before the version in mod_id
-        let (package_name, _) = match mod_id.rsplit_once('-') {
-            Some((name, _ver)) => (name, _ver),
-            None => return Err(AppError::Custom("Invalid mod ID format".to_string())),
-        };
+/// Points enabled plugin links at another version of the same package.
+async fn relink_plugin(plugin_dirs: &[PathBuf], repository_path: &str, from_id: &str, to_id: &str) -> Result<()> {
+    for plugins_dir in plugin_dirs {
+        // symlink_metadata, because the link dangles once the old version has been moved away
+        if plugins_dir.join(from_id).symlink_metadata().is_err() {
+            continue;
+        }
 
-        let new_full_id = format!("{}-{}", package_name, update.latest_version);
+        let plugins_dir = plugins_dir.to_string_lossy().to_string();
+        mod_operations::disable_mod(plugins_dir.clone(), from_id.to_string()).await?;
+        mod_operations::enable_mod(repository_path.to_string(), plugins_dir, to_id.to_string()).await?;
+    }
+    Ok(())
+}
 
-        // 2. Call install_mod for the new version
-        crate::commands::mod_operations::install_mod(
-            state.clone(),
-            repository_path.clone(),
-            new_full_id.clone(),
-            update.download_url.clone()
-        ).await?;
+/// Deletes retained versions whose update is older than the retention period.
+fn prune_retained_versions(state: &AppState, repository_path: &str, retention_days: u32) -> Result<()> {
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(retention_days.into())).to_rfc3339();
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
 
-        // 3. Uninstall old version to prevent duplicates
-        // Note: Check if old version is different from new version (it should be)
-        if mod_id != new_full_id {
-             crate::commands::mod_operations::uninstall_mod(repository_path, mod_id).await?;
-        }
+    let mut stmt = conn.prepare(
+        "SELECT id, package_name, from_version FROM update_history WHERE retained = 1 AND updated_at < ?1"
+    )?;
+    let expired: Vec<(i64, String, String)> = stmt
+        .query_map([&cutoff], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    for (id, package_name, from_version) in expired {
+        let mod_id = format!("{}-{}", package_name, from_version);
+        tracing::info!("Retention expired, removing previous version {}", mod_id);
+        mod_installer::discard_retained(Path::new(repository_path), &mod_id)?;
+        conn.execute("UPDATE update_history SET retained = 0 WHERE id = ?1", [id])?;
     }
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn update_all_mods(state: State<'_, AppState>, repository_path: String) -> Result<()> {
-    tracing::info!("Updating all mods...");
-    let updates = check_updates(state.clone()).await?;
+pub async fn list_update_history(state: State<'_, AppState>) -> Result<Vec<UpdateHistoryEntry>> {
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    let mut stmt = conn.prepare(
+        "SELECT id, package_name, from_version, to_version, updated_at, profile_ids, retained, rolled_back
+         FROM update_history ORDER BY id DESC"
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        let profile_ids: String = row.get(5)?;
+        Ok(UpdateHistoryEntry {
+            id: row.get(0)?,
+            package_name: row.get(1)?,
+            from_version: row.get(2)?,
+            to_version: row.get(3)?,
+            updated_at: row.get(4)?,
+            profile_ids: serde_json::from_str(&profile_ids).unwrap_or_default(),
+            retained: row.get(6)?,
+            rolled_back: row.get(7)?,
+        })
+    })?;
+
+    let mut history = Vec::new();
+    for row in rows {
+        history.push(row?);
+    }
+    Ok(history)
+}
 
-    for update in updates {
-        // We can reuse update_mod logic
-        // But calling update_mod directly might re-fetch updates list which is inefficient but safe
-        // Or we implement logic here.
+/// Restores the version replaced by an update and moves the profiles affected by that
+/// update back to it. The newer version is removed unless another profile still uses it.
+#[tauri::command]
+pub async fn rollback_update(app: AppHandle, state: State<'_, AppState>, repository_path: String, history_id: i64) -> Result<()> {
+    tracing::info!("Rolling back update #{}", history_id);
+    let settings = settings_operations::load_settings(app.clone()).await.map_err(AppError::Custom)?;
+
+    let (package_name, from_version, to_version, profile_ids, retained, rolled_back) = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        conn.query_row(
+            "SELECT package_name, from_version, to_version, profile_ids, retained, rolled_back FROM update_history WHERE id = ?1",
+            [history_id],
+            |row| Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, bool>(4)?,
+                row.get::<_, bool>(5)?,
+            )),
+        ).optional()?.ok_or_else(|| AppError::Custom(format!("Update #{} not found", history_id)))?
+    };
+
+    if rolled_back {
+        return Err(AppError::Custom(format!("Update #{} was already rolled back", history_id)));
+    }
 
-        let (package_name, _) = match update.mod_id.rsplit_once('-') {
-            Some((name, _ver)) => (name, _ver),
-            None => continue,
-        };
+    let profile_ids: Vec<String> = serde_json::from_str(&profile_ids)?;
+    let from_id = format!("{}-{}", package_name, from_version);
+    let to_id = format!("{}-{}", package_name, to_version);
+    let repo = Path::new(&repository_path);
 
-        let new_full_id = format!("{}-{}", package_name, update.latest_version);
+    // Versions other profiles kept using were never moved out of the repository
+    if !retained && !repo.join(&from_id).exists() {
+        return Err(AppError::Custom(format!("{} is no longer retained", from_id)));
+    }
 
-        crate::commands::mod_operations::install_mod(
-            state.clone(),
-            repository_path.clone(),
-            new_full_id.clone(),
-            update.download_url
-        ).await?;
+    mod_installer::restore_version(repo, &from_id)?;
+
+    let url: String = {
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        conn.query_row("SELECT download_url FROM mod_versions WHERE full_name = ?1", [&from_id], |row| row.get(0))
+            .optional()?
+            .unwrap_or_default()
+    };
+    mod_operations::update_db_from_manifest(&state, &repo.join(&from_id), &from_id, &url)?;
+
+    let (still_used, relink) = {
+        let mut conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let tx = conn.transaction()?;
+
+        let action = format!("Rolled back {} to {}", package_name, from_version);
+        for profile_id in &profile_ids {
+            let before = profile_history::snapshot(&tx, profile_id)?;
+            tx.execute(
+                "UPDATE OR REPLACE profile_mods SET mod_id = ?1, version = ?2 WHERE profile_id = ?3 AND mod_id = ?4",
+                (&from_id, &from_version, profile_id, &to_id),
+            )?;
+            profile_history::record(&tx, profile_id, &action, &before)?;
+        }
 
-        if update.mod_id != new_full_id {
-             crate::commands::mod_operations::uninstall_mod(repository_path.clone(), update.mod_id).await?;
+        let still_used: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM profile_mods WHERE mod_id = ?1)",
+            [&to_id],
+            |row| row.get(0),
+        )?;
+        if !still_used {
+            mod_operations::remove_installed_from_db(&tx, &to_id)?;
         }
+
+        let relink = links_follow(&tx, &settings, &profile_ids)?;
+
+        tx.execute("UPDATE update_history SET rolled_back = 1, retained = 0 WHERE id = ?1", [history_id])?;
+        tx.commit()?;
+        (still_used, relink)
+    };
+
+    if relink {
+        relink_plugin(&plugin_dirs(&app, &settings, &profile_ids)?, &repository_path, &to_id, &from_id).await?;
     }
+    if !still_used {
+        mod_operations::uninstall_mod(repository_path, to_id).await?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_update_policy(state: State<'_, AppState>, rule: UpdatePolicyRule) -> Result<()> {
+    tracing::info!("Setting update policy: {:?}", rule);
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    conn.execute(
+        "INSERT OR REPLACE INTO update_policies (profile_id, package_name, policy) VALUES (?1, ?2, ?3)",
+        (
+            rule.profile_id.unwrap_or_default(),
+            rule.package_name.unwrap_or_default(),
+            rule.policy.as_str(),
+        ),
+    )?;
+    Ok(())
+}
 
+#[tauri::command]
+pub async fn remove_update_policy(state: State<'_, AppState>, profile_id: Option<String>, package_name: Option<String>) -> Result<()> {
+    tracing::info!("Removing update policy: {:?} / {:?}", profile_id, package_name);
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    conn.execute(
+        "DELETE FROM update_policies WHERE profile_id = ?1 AND package_name = ?2",
+        (profile_id.unwrap_or_default(), package_name.unwrap_or_default()),
+    )?;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+pub async fn list_update_policies(state: State<'_, AppState>) -> Result<Vec<UpdatePolicyRule>> {
+    let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+    let mut stmt = conn.prepare("SELECT profile_id, package_name, policy FROM update_policies ORDER BY profile_id, package_name")?;
+    let rows = stmt.query_map([], |row| {
+        let profile_id: String = row.get(0)?;
+        let package_name: String = row.get(1)?;
+        let policy: String = row.get(2)?;
+        Ok((profile_id, package_name, policy))
+    })?;
+
+    let mut rules = Vec::new();
+    for row in rows {
+        let (profile_id, package_name, policy) = row?;
+        let Some(policy) = UpdatePolicy::parse(&policy) else {
+            tracing::warn!("Ignoring unknown update policy '{}'", policy);
+            continue;
+        };
+        rules.push(UpdatePolicyRule {
+            profile_id: (!profile_id.is_empty()).then_some(profile_id),
+            package_name: (!package_name.is_empty()).then_some(package_name),
+            policy,
+        });
+    }
+
+    Ok(rules)
+}
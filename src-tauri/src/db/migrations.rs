@@ -0,0 +1,35 @@
+use rusqlite::{Connection, Result};
+
+/// Schema changes that `CREATE TABLE IF NOT EXISTS` cannot apply to existing databases.
+/// Each entry runs once, in order; `PRAGMA user_version` records how many have run.
+const MIGRATIONS: &[&str] = &[
+    // 1: cascade profile deletion to its mod list and drop orphaned rows. The mod_id
+    // foreign key is dropped: profiles may list packages that are neither cached nor installed.
+    "CREATE TABLE profile_mods_new (
+        profile_id TEXT NOT NULL,
+        mod_id TEXT NOT NULL,
+        enabled BOOLEAN NOT NULL DEFAULT 1,
+        version TEXT NOT NULL,
+        PRIMARY KEY (profile_id, mod_id),
+        FOREIGN KEY(profile_id) REFERENCES profiles(id) ON DELETE CASCADE
+    );
+    INSERT INTO profile_mods_new (profile_id, mod_id, enabled, version)
+        SELECT profile_id, mod_id, enabled, version FROM profile_mods
+        WHERE profile_id IN (SELECT id FROM profiles);
+    DROP TABLE profile_mods;
+    ALTER TABLE profile_mods_new RENAME TO profile_mods;",
+];
+
+pub fn run(conn: &Connection) -> Result<()> {
+    let applied: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        tracing::info!("Applying database migration {}", index + 1);
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
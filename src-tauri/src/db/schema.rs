@@ -49,6 +49,16 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
         [],
     )?;
 
+    // Thunderstore categories of each cached package ("Server-side", "Tweaks", ...)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS mod_categories (
+            mod_id TEXT NOT NULL,
+            category TEXT NOT NULL,
+            PRIMARY KEY (mod_id, category)
+        )",
+        [],
+    )?;
+
     conn.execute(
         "CREATE TABLE IF NOT EXISTS profiles (
             id TEXT PRIMARY KEY,
@@ -71,8 +81,110 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
             enabled BOOLEAN NOT NULL DEFAULT 1,
             version TEXT NOT NULL,
             PRIMARY KEY (profile_id, mod_id),
-            FOREIGN KEY(profile_id) REFERENCES profiles(id),
-            FOREIGN KEY(mod_id) REFERENCES mods(id)
+            FOREIGN KEY(profile_id) REFERENCES profiles(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS mod_changelogs (
+            version_full_name TEXT PRIMARY KEY,
+            markdown TEXT NOT NULL,
+            fetched_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Empty profile_id / package_name act as wildcards
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS update_policies (
+            profile_id TEXT NOT NULL DEFAULT '',
+            package_name TEXT NOT NULL DEFAULT '',
+            policy TEXT NOT NULL,
+            PRIMARY KEY (profile_id, package_name)
+        )",
+        [],
+    )?;
+
+    // profile_ids holds a JSON array of the profiles moved to the new version
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS update_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            package_name TEXT NOT NULL,
+            from_version TEXT NOT NULL,
+            to_version TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            profile_ids TEXT NOT NULL DEFAULT '[]',
+            retained BOOLEAN NOT NULL DEFAULT 0,
+            rolled_back BOOLEAN NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    // Source and SHA-256 of the archive each installed version was extracted from
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS package_archives (
+            mod_id TEXT PRIMARY KEY,
+            download_url TEXT NOT NULL,
+            sha256 TEXT NOT NULL,
+            installed_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Reusable starting points for new profiles
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS profile_templates (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL,
+            icon TEXT NOT NULL,
+            color TEXT NOT NULL,
+            created TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS profile_template_mods (
+            template_id TEXT NOT NULL,
+            mod_id TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            version TEXT NOT NULL,
+            PRIMARY KEY (template_id, mod_id),
+            FOREIGN KEY(template_id) REFERENCES profile_templates(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Mod list of a profile before and after every change, as JSON arrays. Entries written
+    // by an undo point at the entry they undid through `undoes`.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS profile_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            profile_id TEXT NOT NULL,
+            changed_at TEXT NOT NULL,
+            action TEXT NOT NULL,
+            mods_before TEXT NOT NULL,
+            mods_after TEXT NOT NULL,
+            undoes INTEGER,
+            FOREIGN KEY(profile_id) REFERENCES profiles(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // One row per game launch; ended_at stays NULL while the game is running
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS play_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            profile_id TEXT,
+            pid INTEGER NOT NULL,
+            started_at TEXT NOT NULL,
+            last_seen_at TEXT NOT NULL,
+            ended_at TEXT,
+            exit_code INTEGER,
+            duration_seconds INTEGER NOT NULL DEFAULT 0,
+            FOREIGN KEY(profile_id) REFERENCES profiles(id) ON DELETE SET NULL
         )",
         [],
     )?;
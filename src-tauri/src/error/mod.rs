@@ -29,6 +29,9 @@ pub enum AppError {
     #[error("Invalid path: {0}")]
     InvalidPath(String),
 
+    #[error("Validation failed: {0}")]
+    Validation(String),
+
     #[error("Checksum mismatch. Expected: {0}, Computed: {1}")]
     ChecksumMismatch(String, String),
 
@@ -36,13 +36,21 @@ fn main() {
 
             let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
+            // Foreign keys are off by default in SQLite and must be enabled per connection
+            conn.pragma_update(None, "foreign_keys", true).map_err(|e| e.to_string())?;
+
             // Create tables
             db::schema::create_tables(&conn).map_err(|e| e.to_string())?;
+            db::migrations::run(&conn).map_err(|e| e.to_string())?;
 
             app.manage(AppState {
                 db: Arc::new(Mutex::new(conn)),
             });
 
+            services::update_scheduler::spawn(app_handle.clone());
+            services::backup_scheduler::spawn(app_handle.clone());
+            services::session_tracker::resume(app_handle.clone());
+
             Ok(())
         })
         .plugin(tauri_plugin_dialog::init())
@@ -65,22 +73,65 @@ fn main() {
             commands::profile_operations::update_profile,
             commands::profile_operations::delete_profile,
             commands::profile_operations::switch_profile,
+            commands::profile_operations::duplicate_profile,
+            commands::profile_operations::save_profile_as_template,
+            commands::profile_operations::create_profile_from_template,
+            commands::profile_operations::list_profile_templates,
+            commands::profile_operations::delete_profile_template,
             commands::profile_operations::list_profiles,
+            commands::profile_operations::get_profile,
+            commands::profile_operations::get_profile_bepinex_path,
             commands::profile_operations::export_profile_to_code,
+            commands::profile_operations::diff_profiles,
+            commands::profile_operations::diff_profile_with_code,
+            commands::profile_operations::merge_profiles,
+            commands::profile_operations::validate_profile,
+            commands::profile_operations::list_profile_history,
+            commands::profile_operations::revert_profile,
+            commands::profile_operations::undo_profile_change,
+            commands::profile_operations::preview_profile_import,
             commands::profile_operations::import_profile_from_code,
+            commands::profile_operations::create_profile_from_server,
+            commands::r2modman_operations::import_r2z,
+            commands::r2modman_operations::import_r2modman_code,
+            commands::r2modman_operations::export_r2z,
+            commands::r2modman_operations::export_r2modman_code,
+            commands::r2modman_operations::detect_r2modman_profiles,
+            commands::r2modman_operations::migrate_r2modman,
+            commands::bundle_operations::list_profile_configs,
+            commands::bundle_operations::export_profile_bundle,
+            commands::bundle_operations::inspect_profile_bundle,
+            commands::bundle_operations::import_profile_bundle,
+            commands::lock_operations::lock_profile,
+            commands::lock_operations::get_profile_lock,
+            commands::lock_operations::check_profile_lock,
+            commands::lock_operations::sync_profile_lock,
             // System operations
             commands::system_operations::detect_valheim_path,
             commands::system_operations::check_bepinex,
             commands::system_operations::install_bepinex,
             commands::system_operations::launch_valheim,
+            commands::system_operations::list_play_sessions,
             // Update operations
             commands::update_operations::check_updates,
+            commands::update_operations::check_profile_updates,
+            commands::update_operations::analyze_update_impact,
             commands::update_operations::update_mod,
             commands::update_operations::update_all_mods,
+            commands::update_operations::set_update_policy,
+            commands::update_operations::remove_update_policy,
+            commands::update_operations::list_update_policies,
+            commands::update_operations::list_update_history,
+            commands::update_operations::rollback_update,
             // Backup operations
             commands::backup_operations::create_backup,
             commands::backup_operations::restore_backup,
             commands::backup_operations::list_backups,
+            commands::backup_operations::delete_backup,
+            commands::backup_operations::list_valheim_saves,
+            commands::backup_operations::backup_valheim_saves,
+            commands::backup_operations::list_backup_saves,
+            commands::backup_operations::restore_valheim_saves,
             // Settings operations
             commands::settings_operations::save_settings,
             commands::settings_operations::load_settings,
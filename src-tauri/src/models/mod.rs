@@ -39,9 +39,97 @@ pub struct Profile {
     pub play_time: u64,
 }
 
+/// A profile together with the state of each mod it lists.
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
+pub struct ProfileDetails {
+    #[serde(flatten)]
+    pub profile: Profile,
+    pub mod_entries: Vec<ProfileMod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileMod {
+    /// Versioned id ("Team-Name-1.0.0").
+    pub mod_id: String,
+    pub package_name: String,
+    /// Display name from the catalog or the package manifest.
+    pub name: String,
+    /// Version the profile is pinned to.
+    pub version: String,
+    pub enabled: bool,
+    /// The pinned version has been installed through Deftheim.
+    pub installed: bool,
+    /// The pinned version's folder is not in the repository, so it cannot be linked. Always
+    /// false while no repository is configured.
+    pub missing: bool,
+}
+
+/// A saved mod list (and, for isolated profiles, configs) that new profiles can start from.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub icon: String,
+    pub color: String,
+    pub mods: Vec<String>,
+    pub created: String,
+}
+
+/// Partial update for a profile; fields left out are kept as they are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfilePatch {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    /// Replaces the profile's whole mod list when present.
+    pub mods: Option<Vec<ProfileModPatch>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileUpdateResult {
+    pub profile: Profile,
+    /// Why linking the new mod list into the plugins folder failed, if the profile is
+    /// active and it did. The update is saved either way.
+    pub relink_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileModPatch {
+    /// Versioned id ("Team-Name-1.0.0") or package name ("Team-Name") when `version` is given.
+    pub mod_id: String,
+    pub enabled: bool,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileSwitchResult {
+    /// Mods newly linked into the plugins folder.
+    pub linked: Vec<String>,
+    /// Mods removed from the plugins folder.
+    pub unlinked: Vec<String>,
+    /// Enabled mods of the profile that are not in the repository and were skipped.
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export)]
 pub struct AppSettings {
     pub valheim_path: String,
     pub bepinex_path: String,
@@ -51,4 +139,632 @@ pub struct AppSettings {
     pub auto_update: bool,
     pub auto_backup: bool,
     pub language: String,
-}
\ No newline at end of file
+    /// Days a replaced package version is kept for rollback after an update.
+    pub update_retention_days: u32,
+    /// Minutes between background update checks while `auto_update` is on.
+    pub update_check_interval_minutes: u32,
+    /// Install updates found by the background check instead of only notifying.
+    pub auto_apply_updates: bool,
+    /// Give every profile its own BepInEx folder under the app data directory instead
+    /// of sharing the one in the game folder.
+    pub isolated_profiles: bool,
+    /// Base URL of the Thunderstore legacy profile API used for r2modman profile codes.
+    pub profile_code_endpoint: String,
+    /// Valheim's data folder holding `worlds_local` and `characters_local`; detected when empty.
+    pub valheim_data_path: String,
+    /// Hours between scheduled backups while `auto_backup` is on; 0 turns them off.
+    pub backup_interval_hours: u32,
+    /// Take a backup before launching the game while `auto_backup` is on.
+    pub backup_on_launch: bool,
+    /// Take a backup when the game exits while `auto_backup` is on.
+    pub backup_on_exit: bool,
+    /// Include worlds and characters in full backups.
+    pub backup_include_saves: bool,
+    /// Automatic backups always kept, newest first.
+    pub backup_keep_last: u32,
+    /// Keep the newest automatic backup of each day for this many days.
+    pub backup_keep_daily_days: u32,
+    /// Keep the newest automatic backup of each week for this many weeks.
+    pub backup_keep_weekly_weeks: u32,
+    /// Prune the oldest automatic backups while the backup folder is larger; 0 for no limit.
+    pub backup_max_total_mb: u64,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            valheim_path: String::new(),
+            bepinex_path: String::new(),
+            repository_path: String::new(),
+            backup_path: String::new(),
+            theme: "dark".to_string(),
+            auto_update: true,
+            auto_backup: true,
+            language: "en".to_string(),
+            update_retention_days: 7,
+            update_check_interval_minutes: 360,
+            auto_apply_updates: false,
+            isolated_profiles: false,
+            profile_code_endpoint: "https://thunderstore.io/api/experimental/legacyprofile".to_string(),
+            valheim_data_path: String::new(),
+            // Automatic backups are opt-in: `auto_backup` was on by default long before
+            // anything read it, so the triggers themselves start off
+            backup_interval_hours: 0,
+            backup_on_launch: false,
+            backup_on_exit: false,
+            backup_include_saves: false,
+            backup_keep_last: 10,
+            backup_keep_daily_days: 7,
+            backup_keep_weekly_weeks: 4,
+            backup_max_total_mb: 0,
+        }
+    }
+}
+
+/// How far `check_updates` may move a package. Variants are ordered from most
+/// to least restrictive, so the strictest of several matching rules is the minimum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum UpdatePolicy {
+    Hold,
+    PatchOnly,
+    Minor,
+    Any,
+}
+
+impl UpdatePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpdatePolicy::Hold => "hold",
+            UpdatePolicy::PatchOnly => "patchOnly",
+            UpdatePolicy::Minor => "minor",
+            UpdatePolicy::Any => "any",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hold" => Some(UpdatePolicy::Hold),
+            "patchOnly" => Some(UpdatePolicy::PatchOnly),
+            "minor" => Some(UpdatePolicy::Minor),
+            "any" => Some(UpdatePolicy::Any),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct UpdatePolicyRule {
+    /// Profile the rule applies to; `None` applies to every profile.
+    pub profile_id: Option<String>,
+    /// Package name ("Team-Name") the rule applies to; `None` applies to every package.
+    pub package_name: Option<String>,
+    pub policy: UpdatePolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct UpdateInfo {
+    pub mod_id: String,
+    pub current_version: String,
+    pub latest_version: String,
+    pub changelog: String,
+    pub download_url: String,
+}
+
+/// A newer version exists but the package's update policy does not allow it.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct HeldUpdate {
+    pub mod_id: String,
+    pub current_version: String,
+    pub latest_version: String,
+    pub policy: UpdatePolicy,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct UpdateReport {
+    pub updates: Vec<UpdateInfo>,
+    pub held: Vec<HeldUpdate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileRef {
+    pub id: String,
+    pub name: String,
+    pub active: bool,
+}
+
+/// An installed package that depends on the package being updated.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct DependentImpact {
+    pub mod_id: String,
+    /// Version of the updated package the dependent declares in its manifest.
+    pub requires_version: String,
+    /// Version the dependent could be updated to alongside, if one is offered.
+    pub update_available: Option<String>,
+}
+
+/// A dependency of the new version that is installed at an older version than it requires.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RequiredDependency {
+    pub package_name: String,
+    pub installed_version: String,
+    pub required_version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct UpdateImpact {
+    pub mod_id: String,
+    pub current_version: String,
+    pub latest_version: String,
+    /// Profiles that will be moved to the new version.
+    pub profiles: Vec<ProfileRef>,
+    pub dependents: Vec<DependentImpact>,
+    pub required_dependencies: Vec<RequiredDependency>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct UpdateHistoryEntry {
+    pub id: i64,
+    pub package_name: String,
+    pub from_version: String,
+    pub to_version: String,
+    pub updated_at: String,
+    /// Profiles whose mod list was moved to the new version.
+    pub profile_ids: Vec<String>,
+    /// Whether the previous version is still kept on disk and can be restored.
+    pub retained: bool,
+    pub rolled_back: bool,
+}
+
+/// Exact package versions a profile resolves to, written to the profile's lockfile.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileLock {
+    pub lock_version: u32,
+    pub profile_name: String,
+    pub generated_at: String,
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct LockedPackage {
+    pub mod_id: String,
+    pub package_name: String,
+    pub version: String,
+    pub enabled: bool,
+    /// Empty for packages that were not installed from Thunderstore.
+    pub download_url: String,
+    /// SHA-256 of the downloaded archive; empty when there is no download URL.
+    pub sha256: String,
+    /// Hash of the extracted files, see `utils::hash::hash_dir`.
+    pub content_sha256: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum LockMismatchKind {
+    /// The locked version is not in the repository.
+    Missing,
+    /// The installed files differ from the ones that were locked.
+    Modified,
+    /// The profile pins another version of the package than the lock.
+    VersionChanged,
+    /// The profile lists a package the lock does not.
+    Unlocked,
+    /// The lock lists a package the profile no longer does.
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct LockMismatch {
+    pub mod_id: String,
+    pub kind: LockMismatchKind,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct LockSyncResult {
+    /// Packages downloaded because they were missing.
+    pub installed: Vec<String>,
+    /// Packages reinstalled because their files no longer matched the lock.
+    pub repaired: Vec<String>,
+    /// Packages already matching the lock.
+    pub verified: Vec<String>,
+    /// Packages that could not be installed, with the reason.
+    pub failed: Vec<String>,
+    /// Why linking the synced mod list into the plugins folder failed, if the profile is
+    /// active and it did. The mod list is synced either way.
+    pub relink_error: Option<String>,
+}
+
+/// What importing a profile would install, resolved against the catalog.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ImportPlan {
+    pub entries: Vec<PlannedMod>,
+    pub unresolved: Vec<UnresolvedMod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PlannedMod {
+    pub mod_id: String,
+    pub package_name: String,
+    pub version: String,
+    pub enabled: bool,
+    /// Empty when the package is already in the repository and not in the catalog.
+    pub download_url: String,
+    /// Already in the repository, nothing to download.
+    pub installed: bool,
+    /// Pulled in as a dependency of another entry rather than listed in the import.
+    pub dependency: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct UnresolvedMod {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ImportResult {
+    pub profile: Profile,
+    /// Packages downloaded into the repository.
+    pub installed: Vec<String>,
+    /// Packages that failed to download, with the reason.
+    pub failed: Vec<String>,
+    pub unresolved: Vec<UnresolvedMod>,
+    /// Imported configs the game will not load because profiles are not isolated. They are
+    /// kept in the profile's own config folder for when isolation is turned on.
+    pub unapplied_configs: Vec<String>,
+}
+
+/// A client profile generated from a dedicated server's plugins.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ServerProfileResult {
+    pub import: ImportResult,
+    /// Packages tagged as server-side only; they are in the profile but disabled.
+    pub server_only: Vec<String>,
+    /// Server plugins that could not be matched to a package, with the reason.
+    pub unmatched: Vec<UnresolvedMod>,
+}
+
+/// A profile of another mod manager that can be migrated.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ExternalProfile {
+    pub name: String,
+    pub path: String,
+    pub mod_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct MigratedProfile {
+    pub source_name: String,
+    /// Packages copied from the other manager instead of downloaded.
+    pub reused: Vec<String>,
+    pub import: ImportResult,
+}
+
+/// Contents of a profile bundle, checked against the configs that importing would touch.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileBundleInfo {
+    pub name: String,
+    pub description: String,
+    pub has_lock: bool,
+    pub plan: ImportPlan,
+    pub configs: Vec<BundleConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BundleConfig {
+    /// Path below `BepInEx/config`, with `/` separators.
+    pub path: String,
+    /// A different file already exists at the destination; it is only replaced when chosen.
+    pub conflict: bool,
+}
+
+/// Differences that would turn the left profile into the right one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileDiff {
+    pub packages: Vec<PackageChange>,
+    pub configs: Vec<ConfigChange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum PackageChangeKind {
+    Added,
+    Removed,
+    /// Different versions; the enabled flags may differ as well.
+    VersionChanged,
+    EnabledChanged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PackageChange {
+    pub package_name: String,
+    pub kind: PackageChangeKind,
+    pub left_version: Option<String>,
+    pub right_version: Option<String>,
+    pub left_enabled: Option<bool>,
+    pub right_enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ConfigChange {
+    /// Path below `BepInEx/config`, with `/` separators.
+    pub file: String,
+    pub section: String,
+    pub key: String,
+    pub left_value: Option<String>,
+    pub right_value: Option<String>,
+}
+
+/// Identifies a config value to merge.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ConfigKey {
+    pub file: String,
+    pub section: String,
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileMergeResult {
+    /// What still differs between the profiles afterwards.
+    pub remaining: ProfileDiff,
+    /// Why merging the config values failed, if it did. The packages are merged either way.
+    pub config_error: Option<String>,
+    /// Why linking the merged mods into the plugins folder failed, if the target profile
+    /// is active and it did.
+    pub relink_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PlaySession {
+    pub id: i64,
+    /// `None` for launches without a profile, or if the profile was deleted since.
+    pub profile_id: Option<String>,
+    pub started_at: String,
+    /// `None` while the game is still running.
+    pub ended_at: Option<String>,
+    /// Exit code of the game; unknown for sessions picked up again after a restart.
+    pub exit_code: Option<i32>,
+    pub duration_seconds: u64,
+}
+
+/// One recorded change to a profile's mod list.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileHistoryEntry {
+    pub id: i64,
+    pub profile_id: String,
+    pub changed_at: String,
+    /// What caused the change, e.g. "Edited mod list" or "Updated Team-Name to 1.2.0".
+    pub action: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Mods whose version or enabled state changed, by versioned id after the change.
+    pub changed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileRevertResult {
+    pub profile: Profile,
+    /// Mod versions downloaded again because they were no longer in the repository.
+    pub installed: Vec<String>,
+    /// Mod versions that could not be restored, with the reason; they stay in the profile
+    /// and show up as missing.
+    pub failed: Vec<String>,
+    /// Why linking the restored mods into the plugins folder failed, if the profile is
+    /// active and it did. The mod list is restored either way.
+    pub relink_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum HealthSeverity {
+    /// The game will most likely fail to load the profile's mods.
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum HealthFindingKind {
+    MissingDependency,
+    /// Several versions of the same package are in the profile.
+    DuplicatePackage,
+    DeprecatedPackage,
+    NotInRepository,
+    /// A plugins folder entry links to a folder that no longer exists.
+    BrokenLink,
+    BepInExVersion,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct HealthFinding {
+    pub kind: HealthFindingKind,
+    pub severity: HealthSeverity,
+    /// The package or plugins folder entry the finding is about.
+    pub subject: Option<String>,
+    pub message: String,
+    pub suggested_fix: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileHealth {
+    pub profile_id: String,
+    /// No finding is an error.
+    pub launchable: bool,
+    /// Ordered by severity, errors first.
+    pub findings: Vec<HealthFinding>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BackupInfo {
+    pub id: String,
+    pub description: String,
+    pub timestamp: String,
+    /// Total size of the backed-up files.
+    pub size: u64,
+    /// Stored bytes no other backup shares; what deleting the backup frees.
+    pub unique_size: u64,
+    /// Taken by the scheduler rather than by hand; only these are pruned by retention rules.
+    pub automatic: bool,
+    /// Files and folders in the backup, relative to the backup root.
+    pub contents: Vec<String>,
+}
+
+/// An automatic backup removed by the retention rules.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PrunedBackup {
+    pub id: String,
+    pub description: String,
+    pub timestamp: String,
+    pub pruned_at: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BackupList {
+    /// Newest first.
+    pub backups: Vec<BackupInfo>,
+    /// Recently pruned backups, newest first.
+    pub pruned: Vec<PrunedBackup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BackupDeleteResult {
+    /// Stored files no remaining backup referred to.
+    pub removed_files: usize,
+    pub freed_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BackupRestoreResult {
+    /// Backup taken right before restoring, to undo the restore with.
+    pub safety_backup: String,
+    /// Profiles whose definition and mod list were restored.
+    pub profiles: Vec<String>,
+    pub config_files: usize,
+    /// Why linking the active profile's restored mods failed, if it did.
+    pub relink_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum SaveKind {
+    World,
+    Character,
+}
+
+/// Identifies a world or character save.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SaveRef {
+    pub kind: SaveKind,
+    pub name: String,
+    /// Stored in the pre-Hearth & Home `worlds` / `characters` folders rather than `*_local`.
+    #[serde(default)]
+    pub legacy: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ValheimSave {
+    pub kind: SaveKind,
+    pub name: String,
+    pub legacy: bool,
+    /// Total size of the save's files.
+    pub size: u64,
+    /// Last time the game wrote the save; for saves in a backup, when the backup was taken.
+    pub modified: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SaveRestoreResult {
+    /// Backup of the replaced saves, taken right before restoring; `None` if none of them
+    /// existed.
+    pub safety_backup: Option<String>,
+    pub restored: Vec<SaveRef>,
+}
@@ -0,0 +1,108 @@
+use crate::commands::{backup_operations, settings_operations};
+use crate::error::{AppError, Result};
+use crate::models::AppSettings;
+use crate::services::backup_service::BackupService;
+use crate::state::AppState;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Delay before the first scheduled check, so startup is not slowed down.
+const STARTUP_DELAY: Duration = Duration::from_secs(120);
+/// How often to check whether a scheduled backup is due; settings are re-read each time.
+const POLL: Duration = Duration::from_secs(300);
+
+/// What an automatic backup is taken for.
+#[derive(Debug, Clone, Copy)]
+pub enum Trigger {
+    Schedule,
+    Launch,
+    Exit,
+}
+
+impl Trigger {
+    fn description(self) -> &'static str {
+        match self {
+            Trigger::Schedule => "Scheduled backup",
+            Trigger::Launch => "Before launching Valheim",
+            Trigger::Exit => "After playing Valheim",
+        }
+    }
+
+    fn enabled(self, settings: &AppSettings) -> bool {
+        settings.auto_backup
+            && match self {
+                Trigger::Schedule => settings.backup_interval_hours > 0,
+                Trigger::Launch => settings.backup_on_launch,
+                Trigger::Exit => settings.backup_on_exit,
+            }
+    }
+}
+
+/// Starts the scheduled backup loop. A backup is taken once the newest automatic one is
+/// older than `backup_interval_hours`, so the schedule carries over app restarts.
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        loop {
+            match settings_operations::load_settings(app.clone()).await {
+                Ok(settings) if Trigger::Schedule.enabled(&settings) => match is_due(&app, &settings) {
+                    Ok(true) => run(&app, Trigger::Schedule).await,
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!("Could not check for a scheduled backup: {}", e),
+                },
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Scheduled backup skipped, could not load settings: {}", e),
+            }
+            tokio::time::sleep(POLL).await;
+        }
+    });
+}
+
+/// Takes an automatic backup if the settings ask for one on `trigger`, then prunes the
+/// automatic backups by the retention rules. The file work runs on a blocking thread.
+/// Failures are logged, never returned, so they do not get in the way of the game.
+pub async fn run(app: &AppHandle, trigger: Trigger) {
+    let settings = match settings_operations::load_settings(app.clone()).await {
+        Ok(settings) => settings,
+        Err(e) => {
+            tracing::warn!("Automatic backup skipped, could not load settings: {}", e);
+            return;
+        }
+    };
+    if !trigger.enabled(&settings) {
+        return;
+    }
+
+    tracing::info!("Taking automatic backup: {:?}", trigger);
+    let app = app.clone();
+    match tauri::async_runtime::spawn_blocking(move || backup_and_prune(&app, &settings, trigger)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("Automatic backup failed: {}", e),
+        Err(e) => tracing::warn!("Automatic backup task failed: {}", e),
+    }
+}
+
+fn backup_and_prune(app: &AppHandle, settings: &AppSettings, trigger: Trigger) -> Result<()> {
+    backup_operations::full_backup(app, settings, trigger.description(), true)?;
+    let pruned = service(app, settings)?.prune(&backup_operations::retention_policy(settings))?;
+    app.emit("backups-changed", &pruned).map_err(|e| AppError::Custom(e.to_string()))?;
+    Ok(())
+}
+
+fn is_due(app: &AppHandle, settings: &AppSettings) -> Result<bool> {
+    let list = service(app, settings)?.list()?;
+    let Some(latest) = list.backups.iter().find(|b| b.automatic) else {
+        return Ok(true);
+    };
+    let taken = DateTime::parse_from_rfc3339(&latest.timestamp)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| AppError::Custom(format!("Invalid backup timestamp '{}': {}", latest.timestamp, e)))?;
+    Ok(Utc::now() - taken >= chrono::Duration::hours(i64::from(settings.backup_interval_hours)))
+}
+
+fn service(app: &AppHandle, settings: &AppSettings) -> Result<BackupService> {
+    let state = app.state::<AppState>();
+    Ok(BackupService::new(state.db.clone(), backup_operations::backup_dir(app, settings)?))
+}
@@ -0,0 +1,850 @@
+use crate::error::{AppError, Result};
+use crate::models::{
+    BackupDeleteResult, BackupInfo, BackupList, BackupRestoreResult, Profile, PrunedBackup, SaveRef, SaveRestoreResult, ValheimSave,
+};
+use crate::services::blob_store::BlobStore;
+use crate::services::{profile_history, profile_manager, valheim_saves};
+use crate::utils::file_ops;
+use chrono::{DateTime, Datelike, Local, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use walkdir::WalkDir;
+
+/// Folder of the backup manifests, one `<id>.json` per backup.
+const SNAPSHOTS: &str = "snapshots";
+/// Folder of the blob store holding the files of all backups.
+const BLOBS: &str = "blobs";
+/// Archive folder of the game's shared BepInEx configs.
+const SHARED_CONFIG: &str = "BepInEx/config";
+const PROFILES: &str = "profiles";
+/// Archive folder of world and character saves, laid out like the game's data folder.
+const SAVES: &str = "saves";
+/// Log of backups removed by the retention rules, shown with the backup list.
+const PRUNED_LOG: &str = "pruned.json";
+/// Entries kept in the pruned log.
+const PRUNED_LOG_LIMIT: usize = 50;
+
+/// Serializes writes to the store with garbage collection, which would otherwise delete the
+/// blobs of a backup whose manifest is not written yet.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Describes a backup. Its files live in the blob store; the manifest only lists them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub id: String,
+    pub description: String,
+    pub timestamp: String,
+    /// Total size of the backed-up files.
+    pub size: u64,
+    pub contents: Vec<String>,
+    pub files: Vec<BackupFile>,
+    /// Taken by the scheduler; only these are subject to the retention rules.
+    pub automatic: bool,
+}
+
+/// A file in a backup: its path inside the backup (`/` separated) and its blob.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// A profile definition together with its mod list, as stored in backups.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfileSnapshot {
+    pub profile: Profile,
+    pub mods: Vec<ProfileModSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfileModSnapshot {
+    pub mod_id: String,
+    pub enabled: bool,
+    pub version: String,
+}
+
+/// Folders a full backup reads from and a restore writes to.
+pub struct BackupSources<'a> {
+    /// The game's shared `BepInEx/config` folder.
+    pub shared_config: Option<&'a Path>,
+    /// App data folder holding the BepInEx roots of isolated profiles.
+    pub app_data: &'a Path,
+    /// Valheim's data folder; all worlds and characters are backed up when given.
+    pub saves_dir: Option<&'a Path>,
+}
+
+/// Which automatic backups to keep. A backup is kept if any rule keeps it; the size limit
+/// then removes the oldest ones left. The newest automatic backup is always kept.
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionPolicy {
+    pub keep_last: usize,
+    /// Newest backup of each day within this many days.
+    pub keep_daily_days: i64,
+    /// Newest backup of each week within this many weeks.
+    pub keep_weekly_weeks: i64,
+    /// Largest size of the backup folder in bytes; 0 for no limit.
+    pub max_total_bytes: u64,
+}
+
+/// Backups are deduplicated: every file goes into a content-addressed blob store below the
+/// backup folder, so files that did not change between backups are stored once, and each
+/// backup is a manifest listing its files.
+pub struct BackupService {
+    db_conn: Arc<Mutex<Connection>>,
+    backup_dir: PathBuf,
+    store: BlobStore,
+}
+
+impl BackupService {
+    pub fn new(db_conn: Arc<Mutex<Connection>>, backup_dir: PathBuf) -> Self {
+        let store = BlobStore::new(backup_dir.join(BLOBS));
+        Self { db_conn, backup_dir, store }
+    }
+
+    /// Backs up a profile's definition and mod list, plus the configs of its own BepInEx
+    /// root when `bepinex_root` is given. Returns the id of the backup.
+    pub fn backup_profile(&self, profile_id: &str, bepinex_root: Option<&Path>, description: &str) -> Result<String> {
+        let snapshot = {
+            let conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+            snapshot_profile(&conn, profile_id)?
+        };
+
+        let manifest = self.write(description, false, |backup| {
+            backup.file(&format!("{}/{}.json", PROFILES, profile_id), &serde_json::to_vec_pretty(&snapshot)?)?;
+            if let Some(config) = bepinex_root.map(|root| root.join("config")).filter(|dir| dir.is_dir()) {
+                backup.dir(&config, &format!("{}/{}/{}", PROFILES, profile_id, SHARED_CONFIG))?;
+            }
+            Ok(())
+        })?;
+
+        tracing::info!("Backed up profile {} as {}", profile_id, manifest.id);
+        Ok(manifest.id)
+    }
+
+    /// Archives every profile with its mod list, the shared BepInEx configs, the configs
+    /// of each isolated profile and, when `sources` has a saves folder, every save.
+    pub fn create(&self, description: &str, sources: &BackupSources, automatic: bool) -> Result<BackupInfo> {
+        let snapshots = {
+            let conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+            let ids: Vec<String> = conn
+                .prepare("SELECT id FROM profiles ORDER BY name COLLATE NOCASE")?
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            ids.iter().map(|id| snapshot_profile(&conn, id)).collect::<Result<Vec<_>>>()?
+        };
+
+        let saves = match sources.saves_dir {
+            Some(dir) => valheim_saves::list(dir)?
+                .into_iter()
+                .map(|s| SaveRef { kind: s.kind, name: s.name, legacy: s.legacy })
+                .collect(),
+            None => vec![],
+        };
+
+        let manifest = self.write(description, automatic, |backup| {
+            for snapshot in &snapshots {
+                let id = &snapshot.profile.id;
+                backup.file(&format!("{}/{}.json", PROFILES, id), &serde_json::to_vec_pretty(snapshot)?)?;
+                let config = profile_manager::profile_bepinex_dir(sources.app_data, id).join("config");
+                if config.is_dir() {
+                    backup.dir(&config, &format!("{}/{}/{}", PROFILES, id, SHARED_CONFIG))?;
+                }
+            }
+            if let Some(config) = sources.shared_config.filter(|dir| dir.is_dir()) {
+                backup.dir(config, SHARED_CONFIG)?;
+            }
+            if let Some(dir) = sources.saves_dir {
+                for save in &saves {
+                    backup.save(dir, save)?;
+                }
+            }
+            Ok(())
+        })?;
+
+        tracing::info!("Created backup {} with {} profiles and {} saves", manifest.id, snapshots.len(), saves.len());
+        Ok(self.info(&manifest, &self.manifests()?))
+    }
+
+    /// Backups in the backup folder, newest first, with the backups recently pruned.
+    pub fn list(&self) -> Result<BackupList> {
+        let manifests = self.manifests()?;
+        let mut backups: Vec<BackupInfo> = manifests.iter().map(|m| self.info(m, &manifests)).collect();
+        backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        Ok(BackupList { backups, pruned: self.pruned_log() })
+    }
+
+    /// Deletes the automatic backups `policy` does not keep, along with the stored files
+    /// only they used. Manual backups are never pruned. Returns the pruned backups.
+    pub fn prune(&self, policy: &RetentionPolicy) -> Result<Vec<PrunedBackup>> {
+        let manifests = self.manifests()?;
+        let _guard = lock_store()?;
+
+        let pruned_at = Utc::now().to_rfc3339();
+        let mut pruned = Vec::new();
+        for (index, reason) in self.select_pruned(&manifests, policy, Local::now()) {
+            let manifest = &manifests[index];
+            pruned.push(PrunedBackup {
+                id: manifest.id.clone(),
+                description: manifest.description.clone(),
+                timestamp: manifest.timestamp.clone(),
+                pruned_at: pruned_at.clone(),
+                reason,
+            });
+        }
+        if pruned.is_empty() {
+            return Ok(pruned);
+        }
+
+        let ids: Vec<&str> = pruned.iter().map(|p| p.id.as_str()).collect();
+        let (removed_files, freed_bytes) = self.remove_backups(&ids)?;
+        tracing::info!("Pruned {} backups, freeing {} bytes in {} files", pruned.len(), freed_bytes, removed_files);
+
+        let mut log = pruned.clone();
+        log.extend(self.pruned_log());
+        log.truncate(PRUNED_LOG_LIMIT);
+        fs::write(self.backup_dir.join(PRUNED_LOG), serde_json::to_vec_pretty(&log)?)?;
+        Ok(pruned)
+    }
+
+    /// Deletes a backup and the stored files no other backup shares.
+    pub fn delete(&self, backup_id: &str) -> Result<BackupDeleteResult> {
+        self.load(backup_id)?;
+        let _guard = lock_store()?;
+        let (removed_files, freed_bytes) = self.remove_backups(&[backup_id])?;
+        tracing::info!("Deleted backup {}, freeing {} bytes in {} files", backup_id, freed_bytes, removed_files);
+        Ok(BackupDeleteResult { removed_files, freed_bytes })
+    }
+
+    /// Restores everything a backup holds: profiles and their mod lists are put back (and
+    /// re-created if they were deleted), config files overwritten. Profiles and files that
+    /// are not in the backup are left alone. A full backup is taken first so the restore
+    /// itself can be undone. Saves are only restored one by one through `restore_saves`.
+    pub fn restore(&self, backup_id: &str, sources: &BackupSources) -> Result<BackupRestoreResult> {
+        let manifest = self.load(backup_id)?;
+
+        let mut snapshots = Vec::new();
+        let mut shared_files = Vec::new();
+        let mut profile_files: Vec<(String, PathBuf, Vec<u8>)> = Vec::new();
+        for file in &manifest.files {
+            let name = file.path.as_str();
+            if !name.starts_with(&format!("{}/", SHARED_CONFIG)) && !name.starts_with(&format!("{}/", PROFILES)) {
+                continue;
+            }
+            let content = self.store.get(&file.sha256)?;
+
+            if let Some(relative) = name.strip_prefix(&format!("{}/", SHARED_CONFIG)).and_then(file_ops::safe_relative_path) {
+                shared_files.push((relative, content));
+            } else if let Some(rest) = name.strip_prefix(&format!("{}/", PROFILES)) {
+                match rest.split_once('/') {
+                    None if rest.ends_with(".json") => snapshots.push(serde_json::from_slice::<ProfileSnapshot>(&content)?),
+                    Some((id, file)) if is_safe_id(id) => {
+                        if let Some(relative) = file.strip_prefix(&format!("{}/", SHARED_CONFIG)).and_then(file_ops::safe_relative_path) {
+                            profile_files.push((id.to_string(), relative, content));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let safety = self.create(&format!("Before restoring backup {}", backup_id), sources, false)?;
+
+        let mut profiles = Vec::new();
+        {
+            let mut conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+            for snapshot in &snapshots {
+                restore_profile(&mut conn, snapshot, &format!("Restored from backup {}", backup_id))?;
+                profiles.push(snapshot.profile.id.clone());
+            }
+        }
+
+        let mut config_files = 0;
+        if let Some(dir) = sources.shared_config {
+            file_ops::write_files(dir, &shared_files)?;
+            config_files += shared_files.len();
+        }
+        for (id, relative, content) in profile_files {
+            let dir = profile_manager::profile_bepinex_dir(sources.app_data, &id).join("config");
+            file_ops::write_files(&dir, &[(relative, content)])?;
+            config_files += 1;
+        }
+
+        tracing::info!("Restored backup {}: {} profiles, {} config files", backup_id, profiles.len(), config_files);
+        Ok(BackupRestoreResult { safety_backup: safety.id, profiles, config_files, relink_error: None })
+    }
+
+    /// Archives the files of the given worlds and characters from Valheim's data folder.
+    pub fn backup_saves(&self, description: &str, data_dir: &Path, saves: &[SaveRef]) -> Result<BackupInfo> {
+        if saves.is_empty() {
+            return Err(AppError::Validation("No worlds or characters selected".to_string()));
+        }
+
+        let manifest = self.write(description, false, |backup| {
+            for save in saves {
+                backup.save(data_dir, save)?;
+            }
+            Ok(())
+        })?;
+
+        tracing::info!("Backed up {} saves as {}", saves.len(), manifest.id);
+        Ok(self.info(&manifest, &self.manifests()?))
+    }
+
+    /// Worlds and characters stored in a backup.
+    pub fn list_saves(&self, backup_id: &str) -> Result<Vec<ValheimSave>> {
+        let manifest = self.load(backup_id)?;
+
+        let mut saves: Vec<ValheimSave> = Vec::new();
+        for file in &manifest.files {
+            let Some((save, _)) = save_entry(&file.path) else {
+                continue;
+            };
+            match saves.iter_mut().find(|s| s.kind == save.kind && s.legacy == save.legacy && s.name == save.name) {
+                Some(existing) => existing.size += file.size,
+                None => saves.push(ValheimSave {
+                    kind: save.kind,
+                    name: save.name,
+                    legacy: save.legacy,
+                    size: file.size,
+                    modified: manifest.timestamp.clone(),
+                }),
+            }
+        }
+        saves.sort_by(|a, b| (a.kind, a.legacy, &a.name).cmp(&(b.kind, b.legacy, &b.name)));
+        Ok(saves)
+    }
+
+    /// Puts the selected worlds and characters back from a backup. Their current files are
+    /// backed up first, then replaced by the backed-up ones.
+    pub fn restore_saves(&self, backup_id: &str, data_dir: &Path, saves: &[SaveRef]) -> Result<SaveRestoreResult> {
+        let manifest = self.load(backup_id)?;
+
+        // Files of each selected save as (file name, content), in the order of `saves`
+        let mut contents: Vec<Vec<(String, Vec<u8>)>> = vec![Vec::new(); saves.len()];
+        for file in &manifest.files {
+            let Some((save, file_name)) = save_entry(&file.path) else {
+                continue;
+            };
+            if let Some(index) = saves.iter().position(|s| *s == save) {
+                contents[index].push((file_name, self.store.get(&file.sha256)?));
+            }
+        }
+        if let Some(index) = contents.iter().position(|files| files.is_empty()) {
+            return Err(AppError::Validation(format!("Backup {} does not contain {}", backup_id, saves[index].name)));
+        }
+
+        let existing: Vec<SaveRef> = saves
+            .iter()
+            .filter(|s| valheim_saves::files(data_dir, s).is_ok_and(|files| !files.is_empty()))
+            .cloned()
+            .collect();
+        let safety_backup = if existing.is_empty() {
+            None
+        } else {
+            Some(self.backup_saves(&format!("Before restoring saves from backup {}", backup_id), data_dir, &existing)?.id)
+        };
+
+        for (save, files) in saves.iter().zip(&contents) {
+            let dir = data_dir.join(valheim_saves::folder(save.kind, save.legacy));
+            for current in valheim_saves::files(data_dir, save)? {
+                fs::remove_file(current)?;
+            }
+            fs::create_dir_all(&dir)?;
+            for (file_name, content) in files {
+                fs::write(dir.join(file_name), content)?;
+            }
+            tracing::info!("Restored {:?} {} from backup {}", save.kind, save.name, backup_id);
+        }
+
+        Ok(SaveRestoreResult { safety_backup, restored: saves.to_vec() })
+    }
+
+    fn load(&self, backup_id: &str) -> Result<BackupManifest> {
+        if !is_safe_id(backup_id) {
+            return Err(AppError::Validation(format!("Invalid backup id '{}'", backup_id)));
+        }
+        let path = self.manifest_path(backup_id);
+        if !path.is_file() {
+            return Err(AppError::Validation(format!("Backup {} does not exist", backup_id)));
+        }
+        read_manifest(&path)
+    }
+
+    /// Manifests of all backups; unreadable ones are skipped so the others can still be listed.
+    fn manifests(&self) -> Result<Vec<BackupManifest>> {
+        let mut manifests = Vec::new();
+        for path in self.manifest_paths()? {
+            match read_manifest(&path) {
+                Ok(manifest) => manifests.push(manifest),
+                Err(e) => tracing::warn!("Skipping backup {}: {}", path.display(), e),
+            }
+        }
+        Ok(manifests)
+    }
+
+    /// Manifests of all backups, failing on the first unreadable one. Garbage collection must
+    /// use this: a skipped manifest would leave its files unreferenced and they would be deleted.
+    fn manifests_strict(&self) -> Result<Vec<BackupManifest>> {
+        self.manifest_paths()?
+            .iter()
+            .map(|path| {
+                read_manifest(path).map_err(|e| {
+                    AppError::Custom(format!("Backup {} cannot be read, not deleting any backup data: {}", path.display(), e))
+                })
+            })
+            .collect()
+    }
+
+    fn manifest_paths(&self) -> Result<Vec<PathBuf>> {
+        let dir = self.backup_dir.join(SNAPSHOTS);
+        if !dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
+
+    fn manifest_path(&self, backup_id: &str) -> PathBuf {
+        self.backup_dir.join(SNAPSHOTS).join(format!("{}.json", backup_id))
+    }
+
+    /// Stores a new backup, filled by `fill`, and writes its manifest.
+    fn write(&self, description: &str, automatic: bool, fill: impl FnOnce(&mut BackupWriter) -> Result<()>) -> Result<BackupManifest> {
+        let _guard = lock_store()?;
+        let now = chrono::Utc::now();
+        let id = format!("{}-{}", now.format("%Y%m%d-%H%M%S"), &uuid::Uuid::new_v4().simple().to_string()[..8]);
+
+        let mut backup = BackupWriter { store: &self.store, files: Vec::new(), contents: Vec::new(), size: 0 };
+        fill(&mut backup)?;
+        let manifest = BackupManifest {
+            id,
+            description: description.to_string(),
+            timestamp: now.to_rfc3339(),
+            size: backup.size,
+            contents: backup.contents,
+            files: backup.files,
+            automatic,
+        };
+        self.save_manifest(&manifest)?;
+        Ok(manifest)
+    }
+
+    /// Writes a manifest under a temporary name first, so a backup is either complete or absent.
+    fn save_manifest(&self, manifest: &BackupManifest) -> Result<()> {
+        let path = self.manifest_path(&manifest.id);
+        fs::create_dir_all(self.backup_dir.join(SNAPSHOTS))?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(manifest)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Automatic backups `policy` does not keep at `now`, as indices into `manifests` with the reason.
+    fn select_pruned(&self, manifests: &[BackupManifest], policy: &RetentionPolicy, now: DateTime<Local>) -> Vec<(usize, String)> {
+        let mut automatic: Vec<(usize, DateTime<Local>)> = manifests
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.automatic)
+            .filter_map(|(i, m)| DateTime::parse_from_rfc3339(&m.timestamp).ok().map(|t| (i, t.with_timezone(&Local))))
+            .collect();
+        automatic.sort_by_key(|(_, time)| std::cmp::Reverse(*time));
+
+        let mut kept = HashSet::new();
+        let mut days = HashSet::new();
+        let mut weeks = HashSet::new();
+        for (position, (index, time)) in automatic.iter().enumerate() {
+            let age = now - *time;
+            let keep_last = position == 0 || position < policy.keep_last;
+            let keep_daily = age.num_days() < policy.keep_daily_days && days.insert(time.date_naive());
+            let week = time.iso_week();
+            let keep_weekly = age.num_weeks() < policy.keep_weekly_weeks && weeks.insert((week.year(), week.week()));
+            if keep_last || keep_daily || keep_weekly {
+                kept.insert(*index);
+            }
+        }
+
+        let mut pruned: Vec<(usize, String)> = automatic
+            .iter()
+            .filter(|(index, _)| !kept.contains(index))
+            .map(|(index, _)| (*index, "Outside the retention rules".to_string()))
+            .collect();
+
+        if policy.max_total_bytes > 0 {
+            // Newest first so popping takes the oldest; the newest automatic backup is spared
+            let mut candidates: Vec<usize> =
+                automatic.iter().skip(1).map(|(index, _)| *index).filter(|index| kept.contains(index)).collect();
+            loop {
+                let remaining: HashSet<&str> = manifests
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !pruned.iter().any(|(p, _)| p == i))
+                    .flat_map(|(_, m)| m.files.iter().map(|f| f.sha256.as_str()))
+                    .collect();
+                let total: u64 = remaining.into_iter().map(|blob| self.store.stored_size(blob)).sum();
+                if total <= policy.max_total_bytes {
+                    break;
+                }
+                let Some(oldest) = candidates.pop() else {
+                    break;
+                };
+                pruned.push((oldest, "Backup folder over its size limit".to_string()));
+            }
+        }
+        pruned
+    }
+
+    /// Recently pruned backups, newest first.
+    fn pruned_log(&self) -> Vec<PrunedBackup> {
+        fs::read(self.backup_dir.join(PRUNED_LOG))
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Deletes the manifests of the given backups, then the blobs no remaining backup refers
+    /// to. Nothing is deleted unless every other manifest can be read. Call with the store
+    /// lock held.
+    fn remove_backups(&self, ids: &[&str]) -> Result<(usize, u64)> {
+        let referenced: HashSet<String> = self
+            .manifests_strict()?
+            .into_iter()
+            .filter(|m| !ids.contains(&m.id.as_str()))
+            .flat_map(|m| m.files.into_iter().map(|f| f.sha256))
+            .collect();
+        for id in ids {
+            fs::remove_file(self.manifest_path(id))?;
+        }
+        self.store.collect_garbage(&referenced)
+    }
+
+    /// Lists a backup, with the stored bytes only it refers to among `all` backups.
+    fn info(&self, manifest: &BackupManifest, all: &[BackupManifest]) -> BackupInfo {
+        let mut shared: HashMap<&str, usize> = HashMap::new();
+        for other in all {
+            let blobs: HashSet<&str> = other.files.iter().map(|f| f.sha256.as_str()).collect();
+            for blob in blobs {
+                *shared.entry(blob).or_default() += 1;
+            }
+        }
+        let own: HashSet<&str> = manifest.files.iter().map(|f| f.sha256.as_str()).collect();
+        let unique_size = own
+            .into_iter()
+            .filter(|blob| shared.get(blob).copied().unwrap_or_default() <= 1)
+            .map(|blob| self.store.stored_size(blob))
+            .sum();
+
+        BackupInfo {
+            id: manifest.id.clone(),
+            description: manifest.description.clone(),
+            timestamp: manifest.timestamp.clone(),
+            size: manifest.size,
+            unique_size,
+            automatic: manifest.automatic,
+            contents: manifest.contents.clone(),
+        }
+    }
+}
+
+struct BackupWriter<'a> {
+    store: &'a BlobStore,
+    files: Vec<BackupFile>,
+    contents: Vec<String>,
+    size: u64,
+}
+
+impl BackupWriter<'_> {
+    /// Adds a file and lists it in the manifest's contents.
+    fn file(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        self.entry(name, data)?;
+        self.contents.push(name.to_string());
+        Ok(())
+    }
+
+    /// Adds a file without listing it; the caller describes it in the contents.
+    fn entry(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        let sha256 = self.store.put(data)?;
+        self.files.push(BackupFile { path: name.to_string(), sha256, size: data.len() as u64 });
+        self.size += data.len() as u64;
+        Ok(())
+    }
+
+    /// Adds the files of a world or character from Valheim's data folder.
+    fn save(&mut self, data_dir: &Path, save: &SaveRef) -> Result<()> {
+        let folder = valheim_saves::folder(save.kind, save.legacy);
+        let files = valheim_saves::files(data_dir, save)?;
+        if files.is_empty() {
+            return Err(AppError::Validation(format!("Save {} not found in {}", save.name, folder)));
+        }
+        for file in files {
+            let file_name = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+            self.entry(&format!("{}/{}/{}", SAVES, folder, file_name), &fs::read(&file)?)?;
+        }
+        self.contents.push(format!("{}/{}/{}", SAVES, folder, save.name));
+        Ok(())
+    }
+
+    /// Adds the files under `dir` below `prefix`, using `/` separators.
+    fn dir(&mut self, dir: &Path, prefix: &str) -> Result<()> {
+        for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(dir).map_err(|e| AppError::Custom(e.to_string()))?;
+            let name = relative.components().fold(prefix.to_string(), |name, part| {
+                format!("{}/{}", name, part.as_os_str().to_string_lossy())
+            });
+            self.entry(&name, &fs::read(entry.path())?)?;
+        }
+        self.contents.push(prefix.to_string());
+        Ok(())
+    }
+}
+
+fn read_manifest(path: &Path) -> Result<BackupManifest> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+pub fn snapshot_profile(conn: &Connection, profile_id: &str) -> Result<ProfileSnapshot> {
+    let profile = profile_manager::load_profile(conn, profile_id)?;
+    let mut stmt = conn.prepare("SELECT mod_id, enabled, version FROM profile_mods WHERE profile_id = ?1 ORDER BY mod_id")?;
+    let mods = stmt
+        .query_map([profile_id], |row| {
+            Ok(ProfileModSnapshot { mod_id: row.get(0)?, enabled: row.get(1)?, version: row.get(2)? })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(ProfileSnapshot { profile, mods })
+}
+
+/// Puts a profile's definition and mod list back. Its active flag, play time and last use
+/// stay as they are; a deleted profile is re-created inactive, renamed if its name has been
+/// taken since.
+fn restore_profile(conn: &mut Connection, snapshot: &ProfileSnapshot, action: &str) -> Result<()> {
+    let profile = &snapshot.profile;
+    let name = restored_name(conn, &profile.name, &profile.id)?;
+    conn.execute(
+        "INSERT INTO profiles (id, name, description, icon, color, active, created, last_used, play_time)
+         VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?7, ?8)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name, description = excluded.description, icon = excluded.icon, color = excluded.color",
+        (&profile.id, &name, &profile.description, &profile.icon, &profile.color, &profile.created, &profile.last_used, profile.play_time),
+    )?;
+
+    let mods: Vec<profile_history::HistoryMod> = snapshot
+        .mods
+        .iter()
+        .map(|m| profile_history::HistoryMod { mod_id: m.mod_id.clone(), enabled: m.enabled, version: m.version.clone() })
+        .collect();
+    profile_history::restore(conn, &profile.id, &mods, action)
+}
+
+fn restored_name(conn: &Connection, name: &str, id: &str) -> Result<String> {
+    let suffixed = (1..100).map(|n| match n {
+        1 => format!("{} (restored)", name),
+        n => format!("{} (restored {})", name, n),
+    });
+    for candidate in std::iter::once(name.to_string()).chain(suffixed) {
+        match profile_manager::validate_name(conn, &candidate, Some(id)) {
+            Err(AppError::Validation(_)) => continue,
+            outcome => return outcome,
+        }
+    }
+    Err(AppError::Validation(format!("No free profile name for '{}'", name)))
+}
+
+/// The save a backup file `saves/<folder>/<file>` belongs to, and the file name.
+fn save_entry(name: &str) -> Option<(SaveRef, String)> {
+    let rest = name.strip_prefix(&format!("{}/", SAVES))?;
+    let (folder, file_name) = rest.split_once('/')?;
+    let (kind, legacy) = valheim_saves::from_folder(folder)?;
+    if file_name.contains('/') {
+        return None;
+    }
+    let save_name = valheim_saves::save_name(file_name, kind)?;
+    Some((SaveRef { kind, name: save_name, legacy }, file_name.to_string()))
+}
+
+/// Backup and profile ids end up in file names; only allow the characters they are made of.
+fn is_safe_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn lock_store() -> Result<MutexGuard<'static, ()>> {
+    STORE_LOCK.lock().map_err(|_| AppError::Custom("Backup store lock poisoned".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn service(dir: &Path) -> BackupService {
+        BackupService::new(Arc::new(Mutex::new(Connection::open_in_memory().unwrap())), dir.to_path_buf())
+    }
+
+    fn backup(service: &BackupService, files: &[(&str, &[u8])]) -> BackupManifest {
+        service
+            .write("Test backup", false, |backup| {
+                for (name, data) in files {
+                    backup.file(name, data)?;
+                }
+                Ok(())
+            })
+            .unwrap()
+    }
+
+    /// A Wednesday noon, so day and ISO week boundaries in the retention tests are predictable.
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap()
+    }
+
+    fn automatic(id: &str, age: Duration) -> BackupManifest {
+        BackupManifest {
+            id: id.to_string(),
+            description: "Scheduled backup".to_string(),
+            timestamp: (now() - age).to_rfc3339(),
+            size: 0,
+            contents: vec![],
+            files: vec![],
+            automatic: true,
+        }
+    }
+
+    fn policy(keep_last: usize, keep_daily_days: i64, keep_weekly_weeks: i64, max_total_bytes: u64) -> RetentionPolicy {
+        RetentionPolicy { keep_last, keep_daily_days, keep_weekly_weeks, max_total_bytes }
+    }
+
+    /// Ids of the backups `select_pruned` removes, sorted.
+    fn pruned_ids(service: &BackupService, manifests: &[BackupManifest], policy: RetentionPolicy) -> Vec<String> {
+        let mut ids: Vec<String> =
+            service.select_pruned(manifests, &policy, now()).into_iter().map(|(i, _)| manifests[i].id.clone()).collect();
+        ids.sort();
+        ids
+    }
+
+    fn sha(manifest: &BackupManifest, path: &str) -> String {
+        manifest.files.iter().find(|f| f.path == path).unwrap().sha256.clone()
+    }
+
+    #[test]
+    fn delete_keeps_blobs_shared_with_other_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path());
+        let first = backup(&service, &[("shared.cfg", b"shared"), ("own.cfg", b"only in the first")]);
+        let second = backup(&service, &[("shared.cfg", b"shared")]);
+
+        let result = service.delete(&first.id).unwrap();
+
+        assert_eq!(result.removed_files, 1);
+        assert_eq!(service.store.get(&sha(&second, "shared.cfg")).unwrap(), b"shared");
+        assert!(service.store.get(&sha(&first, "own.cfg")).is_err());
+        let ids: Vec<String> = service.list().unwrap().backups.into_iter().map(|b| b.id).collect();
+        assert_eq!(ids, [second.id]);
+    }
+
+    #[test]
+    fn delete_leaves_everything_when_another_manifest_is_unreadable() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path());
+        let first = backup(&service, &[("a.cfg", b"first")]);
+        let second = backup(&service, &[("b.cfg", b"second")]);
+        fs::write(service.manifest_path(&second.id), b"{\"id\": \"trunc").unwrap();
+
+        assert!(service.delete(&first.id).is_err());
+
+        assert!(service.manifest_path(&first.id).is_file());
+        assert_eq!(service.store.get(&sha(&first, "a.cfg")).unwrap(), b"first");
+        assert_eq!(service.store.get(&sha(&second, "b.cfg")).unwrap(), b"second");
+        // Listing still works, skipping the unreadable backup
+        assert_eq!(service.list().unwrap().backups.len(), 1);
+    }
+
+    #[test]
+    fn unique_size_counts_only_blobs_no_other_backup_uses() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path());
+        let first = backup(&service, &[("shared.cfg", b"shared"), ("own.cfg", b"only in the first")]);
+        let second = backup(&service, &[("shared.cfg", b"shared")]);
+        let all = service.manifests().unwrap();
+
+        assert_eq!(service.info(&first, &all).unique_size, service.store.stored_size(&sha(&first, "own.cfg")));
+        assert_eq!(service.info(&second, &all).unique_size, 0);
+    }
+
+    #[test]
+    fn retention_keeps_the_last_n_automatic_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifests: Vec<BackupManifest> = (0..5).map(|h| automatic(&format!("h{}", h), Duration::hours(h))).collect();
+
+        assert_eq!(pruned_ids(&service(dir.path()), &manifests, policy(2, 0, 0, 0)), ["h2", "h3", "h4"]);
+    }
+
+    #[test]
+    fn retention_never_prunes_manual_backups_or_the_newest_automatic_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manual = automatic("manual", Duration::days(400));
+        manual.automatic = false;
+        let manifests = vec![manual, automatic("newest", Duration::hours(1)), automatic("older", Duration::hours(2))];
+
+        assert_eq!(pruned_ids(&service(dir.path()), &manifests, policy(0, 0, 0, 0)), ["older"]);
+    }
+
+    #[test]
+    fn retention_keeps_the_newest_backup_of_each_recent_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manifests = Vec::new();
+        for day in 0..10 {
+            manifests.push(automatic(&format!("d{}-late", day), Duration::days(day)));
+            manifests.push(automatic(&format!("d{}-early", day), Duration::days(day) + Duration::hours(1)));
+        }
+
+        let pruned = pruned_ids(&service(dir.path()), &manifests, policy(0, 7, 0, 0));
+        let mut kept: Vec<String> = manifests.iter().map(|m| m.id.clone()).filter(|id| !pruned.contains(id)).collect();
+        kept.sort();
+        assert_eq!(kept, ["d0-late", "d1-late", "d2-late", "d3-late", "d4-late", "d5-late", "d6-late"]);
+    }
+
+    #[test]
+    fn retention_keeps_the_newest_backup_of_each_recent_week() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifests: Vec<BackupManifest> = (0..30).map(|day| automatic(&format!("d{:02}", day), Duration::days(day))).collect();
+
+        let pruned = pruned_ids(&service(dir.path()), &manifests, policy(0, 0, 2, 0));
+        let kept: Vec<&str> = manifests.iter().map(|m| m.id.as_str()).filter(|id| !pruned.iter().any(|p| p == id)).collect();
+        // Wednesday of the current week, then the Sundays ending the two weeks before
+        assert_eq!(kept, ["d00", "d03", "d10"]);
+    }
+
+    #[test]
+    fn retention_prunes_the_oldest_automatic_backups_over_the_size_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path());
+        let mut manifests = Vec::new();
+        for (hours, id, data) in [(0, "newest", b"newest data"), (1, "middle", b"middle data"), (2, "oldest", b"oldest data")] {
+            let mut manifest = service.write("Scheduled backup", true, |backup| backup.file("data.bin", data)).unwrap();
+            manifest.id = id.to_string();
+            manifest.timestamp = (now() - Duration::hours(hours)).to_rfc3339();
+            manifests.push(manifest);
+        }
+        let stored = |id: &str| service.store.stored_size(&sha(manifests.iter().find(|m| m.id == id).unwrap(), "data.bin"));
+
+        let limit = stored("newest") + stored("middle");
+        let selected = service.select_pruned(&manifests, &policy(10, 0, 0, limit), now());
+        assert_eq!(selected.len(), 1);
+        assert_eq!(manifests[selected[0].0].id, "oldest");
+        assert_eq!(selected[0].1, "Backup folder over its size limit");
+
+        // However small the limit, the newest automatic backup stays
+        assert_eq!(pruned_ids(&service, &manifests, policy(10, 0, 0, 1)), ["middle", "oldest"]);
+    }
+}
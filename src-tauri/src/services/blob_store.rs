@@ -0,0 +1,170 @@
+use crate::error::{AppError, Result};
+use crate::utils::hash;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+/// Content-addressed file store: each distinct content is kept once, gzip-compressed, at
+/// `<root>/<first two hex digits>/<sha256>`.
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+impl BlobStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Stores `data` unless a blob with the same content exists, returning its hash.
+    pub fn put(&self, data: &[u8]) -> Result<String> {
+        let sha256 = hash::sha256_hex(data);
+        let path = self.path(&sha256);
+        if path.exists() {
+            return Ok(sha256);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        // Written under a temporary name so an interrupted write never leaves a blob
+        // whose content does not match its name
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, encoder.finish()?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(sha256)
+    }
+
+    /// Reads a blob back, checking its content against the hash.
+    pub fn get(&self, sha256: &str) -> Result<Vec<u8>> {
+        let compressed = fs::read(self.path(sha256))
+            .map_err(|e| AppError::Custom(format!("Backup data {} is missing: {}", sha256, e)))?;
+        let mut data = Vec::new();
+        GzDecoder::new(&compressed[..]).read_to_end(&mut data)?;
+        if hash::sha256_hex(&data) != sha256 {
+            return Err(AppError::ChecksumMismatch(sha256.to_string(), hash::sha256_hex(&data)));
+        }
+        Ok(data)
+    }
+
+    /// Bytes a blob takes on disk.
+    pub fn stored_size(&self, sha256: &str) -> u64 {
+        fs::metadata(self.path(sha256)).map(|m| m.len()).unwrap_or(0)
+    }
+
+    /// Deletes every blob not in `referenced`, along with leftovers of interrupted writes.
+    /// Returns the number of files removed and the bytes freed.
+    pub fn collect_garbage(&self, referenced: &HashSet<String>) -> Result<(usize, u64)> {
+        let (mut removed, mut freed) = (0, 0);
+        if !self.root.is_dir() {
+            return Ok((removed, freed));
+        }
+
+        for shard in fs::read_dir(&self.root)? {
+            let shard = shard?.path();
+            if !shard.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&shard)? {
+                let path = entry?.path();
+                let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                if referenced.contains(&name) {
+                    continue;
+                }
+                freed += fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+            if fs::read_dir(&shard)?.next().is_none() {
+                fs::remove_dir(&shard)?;
+            }
+        }
+        Ok((removed, freed))
+    }
+
+    fn path(&self, sha256: &str) -> PathBuf {
+        self.root.join(sha256.get(..2).unwrap_or("00")).join(sha256)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use walkdir::WalkDir;
+
+    fn store() -> (tempfile::TempDir, BlobStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path().join("blobs"));
+        (dir, store)
+    }
+
+    fn file_count(dir: &std::path::Path) -> usize {
+        WalkDir::new(dir).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()).count()
+    }
+
+    #[test]
+    fn put_and_get_round_trip() {
+        let (_dir, store) = store();
+        let sha256 = store.put(b"hello world").unwrap();
+        assert_eq!(sha256, hash::sha256_hex(b"hello world"));
+        assert_eq!(store.get(&sha256).unwrap(), b"hello world");
+        assert!(store.stored_size(&sha256) > 0);
+    }
+
+    #[test]
+    fn put_stores_identical_content_once() {
+        let (dir, store) = store();
+        let first = store.put(b"same").unwrap();
+        let second = store.put(b"same").unwrap();
+        assert_eq!(first, second);
+        assert_eq!(file_count(dir.path()), 1);
+    }
+
+    #[test]
+    fn get_rejects_content_that_does_not_match_the_hash() {
+        let (_dir, store) = store();
+        let sha256 = store.put(b"original").unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"tampered").unwrap();
+        fs::write(store.path(&sha256), encoder.finish().unwrap()).unwrap();
+
+        assert!(matches!(store.get(&sha256), Err(AppError::ChecksumMismatch(..))));
+    }
+
+    #[test]
+    fn get_fails_for_a_missing_blob() {
+        let (_dir, store) = store();
+        assert!(store.get(&hash::sha256_hex(b"never stored")).is_err());
+    }
+
+    #[test]
+    fn collect_garbage_keeps_referenced_blobs_only() {
+        let (_dir, store) = store();
+        let kept = store.put(b"kept").unwrap();
+        let shared = store.put(b"shared").unwrap();
+        let unused = store.put(b"unused").unwrap();
+        let leftover = store.path(&kept).with_extension("tmp");
+        fs::write(&leftover, b"interrupted write").unwrap();
+
+        let referenced: HashSet<String> = [kept.clone(), shared.clone()].into_iter().collect();
+        let (removed, freed) = store.collect_garbage(&referenced).unwrap();
+
+        assert_eq!(removed, 2);
+        assert!(freed > 0);
+        assert_eq!(store.get(&kept).unwrap(), b"kept");
+        assert_eq!(store.get(&shared).unwrap(), b"shared");
+        assert!(store.get(&unused).is_err());
+        assert!(!leftover.exists());
+    }
+
+    #[test]
+    fn collect_garbage_on_a_missing_store_does_nothing() {
+        let (_dir, store) = store();
+        assert_eq!(store.collect_garbage(&HashSet::new()).unwrap(), (0, 0));
+    }
+}
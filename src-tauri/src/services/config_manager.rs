@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+/// Values of a BepInEx `.cfg` file keyed by `(section, key)`. Comments (`#`, `;`) and
+/// lines that are not `key = value` pairs are ignored.
+pub fn parse_cfg(content: &str) -> BTreeMap<(String, String), String> {
+    let mut values = BTreeMap::new();
+    let mut section = String::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim().to_string();
+        } else if let Some((key, value)) = line.split_once('=') {
+            values.insert((section.clone(), key.trim().to_string()), value.trim().to_string());
+        }
+    }
+    values
+}
+
+/// Sets (or with `None` removes) one value in a `.cfg` file, keeping every other line,
+/// comment and the original line endings as they are. Missing keys are appended to their
+/// section and missing sections to the end of the file.
+pub fn set_value(content: &str, section: &str, key: &str, value: Option<&str>) -> String {
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    let mut current = String::new();
+    let mut section_end = None;
+    let mut found = None;
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = name.trim().to_string();
+            continue;
+        }
+        if current != section {
+            continue;
+        }
+        if !trimmed.is_empty() {
+            section_end = Some(index);
+        }
+        let is_key = !trimmed.starts_with('#')
+            && !trimmed.starts_with(';')
+            && trimmed.split_once('=').is_some_and(|(k, _)| k.trim() == key);
+        if is_key {
+            found = Some(index);
+        }
+    }
+
+    match (found, value) {
+        (Some(index), Some(value)) => lines[index] = format!("{} = {}", key, value),
+        (Some(index), None) => {
+            lines.remove(index);
+        }
+        (None, Some(value)) => {
+            let entry = format!("{} = {}", key, value);
+            match section_end.or_else(|| section_header(&lines, section)) {
+                Some(index) => lines.insert(index + 1, entry),
+                None => {
+                    if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                        lines.push(String::new());
+                    }
+                    lines.push(format!("[{}]", section));
+                    lines.push(entry);
+                }
+            }
+        }
+        (None, None) => {}
+    }
+
+    let mut result = lines.join(newline);
+    result.push_str(newline);
+    result
+}
+
+fn section_header(lines: &[String], section: &str) -> Option<usize> {
+    lines.iter().position(|line| {
+        line.trim()
+            .strip_prefix('[')
+            .and_then(|l| l.strip_suffix(']'))
+            .is_some_and(|name| name.trim() == section)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CFG: &str = "## Settings file\n[General]\n# Whether the mod is on\nEnabled = true\nSpeed = 1.5\n\n[Keys]\nJump = Space\n";
+
+    #[test]
+    fn set_value_replaces_in_place_and_keeps_other_lines() {
+        let result = set_value(CFG, "General", "Speed", Some("2"));
+        assert_eq!(result, CFG.replace("Speed = 1.5", "Speed = 2"));
+    }
+
+    #[test]
+    fn set_value_none_removes_only_that_key() {
+        let result = set_value(CFG, "General", "Enabled", Some("false"));
+        assert!(result.contains("Enabled = false"));
+        let result = set_value(CFG, "General", "Enabled", None);
+        assert_eq!(result, CFG.replace("Enabled = true\n", ""));
+    }
+
+    #[test]
+    fn set_value_appends_missing_key_to_its_section() {
+        let result = set_value(CFG, "General", "Volume", Some("0.8"));
+        assert_eq!(result, CFG.replace("Speed = 1.5\n", "Speed = 1.5\nVolume = 0.8\n"));
+    }
+
+    #[test]
+    fn set_value_appends_missing_section_to_the_end() {
+        let result = set_value("[A]\nx = 1\n", "B", "y", Some("2"));
+        assert_eq!(result, "[A]\nx = 1\n\n[B]\ny = 2\n");
+    }
+
+    #[test]
+    fn set_value_ignores_commented_out_keys() {
+        let result = set_value("[A]\n# x = 1\n", "A", "x", Some("2"));
+        assert_eq!(result, "[A]\n# x = 1\nx = 2\n");
+    }
+
+    #[test]
+    fn set_value_keeps_crlf_line_endings() {
+        let result = set_value("[A]\r\nx = 1\r\n", "A", "x", Some("2"));
+        assert_eq!(result, "[A]\r\nx = 2\r\n");
+    }
+
+    #[test]
+    fn set_value_round_trips_through_parse_cfg() {
+        let values = parse_cfg(&set_value(CFG, "Keys", "Crouch", Some("LeftControl")));
+        assert_eq!(values.get(&("Keys".to_string(), "Crouch".to_string())).map(String::as_str), Some("LeftControl"));
+        assert_eq!(values.get(&("General".to_string(), "Speed".to_string())).map(String::as_str), Some("1.5"));
+    }
+}
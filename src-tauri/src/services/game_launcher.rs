@@ -0,0 +1,102 @@
+use crate::commands::system_operations::VALHEIM_APP_ID;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PRELOADER: &str = "BepInEx.Preloader.dll";
+
+/// Doorstop 3 and 4 use different option names for the assembly to load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DoorstopVersion {
+    V3,
+    V4,
+}
+
+/// Builds a command that starts the game with BepInEx loaded from `bepinex_root` instead of
+/// the game folder. Doorstop is redirected through its command line options (Windows) and
+/// environment variables (Linux/macOS), so `doorstop_config.ini` in the game install is left as is.
+pub fn isolated_command(valheim_path: &Path, exe_path: &Path, bepinex_root: &Path) -> Command {
+    let target = bepinex_root.join("core").join(PRELOADER);
+    let mut command = command(valheim_path, exe_path);
+
+    match detect_doorstop(valheim_path) {
+        DoorstopVersion::V4 => {
+            command
+                .arg("--doorstop-enabled").arg("true")
+                .arg("--doorstop-target-assembly").arg(&target)
+                .env("DOORSTOP_ENABLED", "1")
+                .env("DOORSTOP_TARGET_ASSEMBLY", &target);
+        }
+        DoorstopVersion::V3 => {
+            command
+                .arg("--doorstop-enable").arg("true")
+                .arg("--doorstop-target").arg(&target)
+                .env("DOORSTOP_ENABLE", "TRUE")
+                .env("DOORSTOP_INVOKE_DLL_PATH", &target);
+        }
+    }
+
+    // On Unix Doorstop is injected by the start script; launching the binary directly
+    // means preloading the library ourselves.
+    #[cfg(target_os = "linux")]
+    if let Some(lib) = find_doorstop_lib(valheim_path, &["libdoorstop.so", "libdoorstop_x64.so"]) {
+        let lib_dir = lib.parent().map(Path::to_path_buf).unwrap_or_default();
+        let library_path = match std::env::var_os("LD_LIBRARY_PATH") {
+            Some(existing) => format!("{}:{}", lib_dir.display(), existing.to_string_lossy()),
+            None => lib_dir.display().to_string(),
+        };
+        command.env("LD_LIBRARY_PATH", library_path).env("LD_PRELOAD", &lib);
+    }
+    #[cfg(target_os = "macos")]
+    if let Some(lib) = find_doorstop_lib(valheim_path, &["libdoorstop.dylib", "libdoorstop_x64.dylib"]) {
+        command.env("DYLD_INSERT_LIBRARIES", &lib);
+    }
+
+    command
+}
+
+/// Builds a command that starts the game as installed, with the shared BepInEx folder.
+/// Started outside Steam, the game would restart itself through Steam and lose the
+/// arguments and environment set here; `SteamAppId` tells it Steam already knows the app.
+pub fn command(valheim_path: &Path, exe_path: &Path) -> Command {
+    let mut command = Command::new(executable(exe_path));
+    command.current_dir(valheim_path).env("SteamAppId", VALHEIM_APP_ID.to_string());
+    command
+}
+
+/// macOS ships the game as an app bundle; the binary lives inside it.
+fn executable(exe_path: &Path) -> PathBuf {
+    if exe_path.is_dir() {
+        let macos = exe_path.join("Contents").join("MacOS");
+        if let Some(binary) = fs::read_dir(&macos).ok().and_then(|mut entries| entries.find_map(|e| e.ok())) {
+            return binary.path();
+        }
+    }
+    exe_path.to_path_buf()
+}
+
+fn detect_doorstop(valheim_path: &Path) -> DoorstopVersion {
+    if valheim_path.join(".doorstop_version").exists() {
+        return DoorstopVersion::V4;
+    }
+
+    let markers = [
+        ("doorstop_config.ini", "target_assembly"),
+        ("start_game_bepinex.sh", "DOORSTOP_TARGET_ASSEMBLY"),
+        ("run_bepinex.sh", "DOORSTOP_TARGET_ASSEMBLY"),
+    ];
+    for (file, marker) in markers {
+        if fs::read_to_string(valheim_path.join(file)).is_ok_and(|content| content.contains(marker)) {
+            return DoorstopVersion::V4;
+        }
+    }
+    DoorstopVersion::V3
+}
+
+#[cfg(unix)]
+fn find_doorstop_lib(valheim_path: &Path, names: &[&str]) -> Option<PathBuf> {
+    [valheim_path.join("doorstop_libs"), valheim_path.to_path_buf()]
+        .iter()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.exists())
+}
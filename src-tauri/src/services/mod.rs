@@ -1,9 +1,23 @@
 pub mod mod_scanner;
 pub mod mod_installer;
 pub mod profile_manager;
+pub mod profile_lock;
+pub mod profile_import;
+pub mod r2modman;
+pub mod profile_bundle;
+pub mod profile_diff;
+pub mod profile_health;
+pub mod profile_history;
+pub mod server_import;
 pub mod config_manager;
 pub mod update_checker;
+pub mod update_scheduler;
 pub mod backup_service;
+pub mod backup_scheduler;
+pub mod blob_store;
 pub mod download_manager;
+pub mod game_launcher;
+pub mod valheim_saves;
+pub mod session_tracker;
 pub mod thunderstore;
 pub mod thunderstore_service;
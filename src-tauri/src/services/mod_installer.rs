@@ -0,0 +1,105 @@
+use crate::error::{AppError, Result};
+use crate::models::AppSettings;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Folder inside the repository where replaced package versions are kept for rollback.
+/// It has no manifest.json, so `scan_mods` never lists it as a package.
+const RETAINED_DIR: &str = ".rollback";
+
+fn retained_path(repository_path: &Path, mod_id: &str) -> PathBuf {
+    repository_path.join(RETAINED_DIR).join(mod_id)
+}
+
+/// Folder inside the repository where a package is reinstalled before it replaces the
+/// installed copy, which other profiles may be using meanwhile.
+const STAGING_DIR: &str = ".staging";
+
+/// Repository root to install a fresh copy of a package into, next to the installed one.
+pub fn staging_root(repository_path: &Path) -> PathBuf {
+    repository_path.join(STAGING_DIR)
+}
+
+/// Moves the staged copy of a package in place of the installed one. The installed copy
+/// is only deleted once the staged one is in place, and is put back if that fails.
+pub fn replace_with_staged(repository_path: &Path, mod_id: &str) -> Result<()> {
+    let staged = staging_root(repository_path).join(mod_id);
+    let previous = staging_root(repository_path).join(format!("{}.previous", mod_id));
+    let target = repository_path.join(mod_id);
+
+    if previous.exists() {
+        fs::remove_dir_all(&previous)?;
+    }
+    fs::rename(&target, &previous)?;
+    if let Err(e) = fs::rename(&staged, &target) {
+        fs::rename(&previous, &target)?;
+        return Err(e.into());
+    }
+    fs::remove_dir_all(previous)?;
+    Ok(())
+}
+
+/// Deletes the staged copy of a package, if any.
+pub fn discard_staged(repository_path: &Path, mod_id: &str) -> Result<()> {
+    let path = staging_root(repository_path).join(mod_id);
+    if path.exists() {
+        fs::remove_dir_all(path)?;
+    }
+    Ok(())
+}
+
+/// Moves an installed package out of the repository into the rollback area.
+/// Returns false if the package folder did not exist.
+pub fn retain_version(repository_path: &Path, mod_id: &str) -> Result<bool> {
+    let source = repository_path.join(mod_id);
+    if !source.exists() {
+        return Ok(false);
+    }
+
+    let target = retained_path(repository_path, mod_id);
+    if target.exists() {
+        fs::remove_dir_all(&target)?;
+    }
+    fs::create_dir_all(repository_path.join(RETAINED_DIR))?;
+    fs::rename(source, target)?;
+    Ok(true)
+}
+
+/// Moves a retained package version back into the repository.
+pub fn restore_version(repository_path: &Path, mod_id: &str) -> Result<()> {
+    let source = retained_path(repository_path, mod_id);
+    let target = repository_path.join(mod_id);
+    if target.exists() {
+        // Same version is still in use or was reinstalled; any retained copy is redundant
+        if source.exists() {
+            fs::remove_dir_all(source)?;
+        }
+        return Ok(());
+    }
+
+    if !source.exists() {
+        return Err(AppError::ModNotFound(format!("{} (retained copy no longer exists)", mod_id)));
+    }
+    fs::rename(source, target)?;
+    Ok(())
+}
+
+/// Permanently deletes a retained package version.
+pub fn discard_retained(repository_path: &Path, mod_id: &str) -> Result<()> {
+    let path = retained_path(repository_path, mod_id);
+    if path.exists() {
+        fs::remove_dir_all(path)?;
+    }
+    Ok(())
+}
+
+/// Resolves the BepInEx plugins folder from settings, falling back to the game folder.
+pub fn plugins_dir(settings: &AppSettings) -> Option<PathBuf> {
+    if !settings.bepinex_path.is_empty() {
+        return Some(Path::new(&settings.bepinex_path).join("plugins"));
+    }
+    if !settings.valheim_path.is_empty() {
+        return Some(Path::new(&settings.valheim_path).join("BepInEx").join("plugins"));
+    }
+    None
+}
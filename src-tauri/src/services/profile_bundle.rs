@@ -0,0 +1,141 @@
+use crate::error::{AppError, Result};
+use crate::models::{Profile, ProfileLock};
+use crate::utils::file_ops;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+pub const BUNDLE_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+const LOCK_FILE: &str = "profile.lock.json";
+const CONFIG_PREFIX: &str = "config/";
+
+/// `manifest.json` of a profile bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub bundle_version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub icon: String,
+    #[serde(default)]
+    pub color: String,
+    pub exported_at: String,
+    pub mods: Vec<BundleMod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleMod {
+    pub mod_id: String,
+    pub enabled: bool,
+}
+
+pub struct Bundle {
+    pub manifest: BundleManifest,
+    pub lock: Option<ProfileLock>,
+    /// Config files keyed by their path below `BepInEx/config`.
+    pub configs: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl BundleManifest {
+    pub fn new(profile: &Profile, mods: Vec<BundleMod>) -> Self {
+        Self {
+            bundle_version: BUNDLE_VERSION,
+            name: profile.name.clone(),
+            description: profile.description.clone(),
+            icon: profile.icon.clone(),
+            color: profile.color.clone(),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            mods,
+        }
+    }
+}
+
+pub fn read(data: &[u8]) -> Result<Bundle> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(zip_error)?;
+    let mut manifest = None;
+    let mut lock = None;
+    let mut configs = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(zip_error)?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().replace('\\', "/");
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+
+        if name == MANIFEST_FILE {
+            manifest = Some(serde_json::from_slice::<BundleManifest>(&content)?);
+        } else if name == LOCK_FILE {
+            lock = Some(serde_json::from_slice::<ProfileLock>(&content)?);
+        } else if let Some(relative) = name.strip_prefix(CONFIG_PREFIX).and_then(file_ops::safe_relative_path) {
+            configs.push((relative, content));
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| AppError::Custom("Not a profile bundle: manifest.json is missing".to_string()))?;
+    if manifest.bundle_version > BUNDLE_VERSION {
+        return Err(AppError::Custom(format!(
+            "Bundle version {} is newer than supported ({})",
+            manifest.bundle_version, BUNDLE_VERSION
+        )));
+    }
+    Ok(Bundle { manifest, lock, configs })
+}
+
+/// Builds a bundle. Of the files below `config_dir`, only those whose relative path is in
+/// `selected` are included, or all of them when `selected` is `None`.
+pub fn write(manifest: &BundleManifest, lock: Option<&ProfileLock>, config_dir: Option<&Path>, selected: Option<&[String]>) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+
+    zip.start_file(MANIFEST_FILE, options).map_err(zip_error)?;
+    zip.write_all(&serde_json::to_vec_pretty(manifest)?)?;
+
+    if let Some(lock) = lock {
+        zip.start_file(LOCK_FILE, options).map_err(zip_error)?;
+        zip.write_all(&serde_json::to_vec_pretty(lock)?)?;
+    }
+
+    if let Some(dir) = config_dir {
+        for relative in list_configs(dir)? {
+            if selected.is_some_and(|selected| !selected.contains(&relative)) {
+                continue;
+            }
+            zip.start_file(format!("{}{}", CONFIG_PREFIX, relative), options).map_err(zip_error)?;
+            zip.write_all(&fs::read(dir.join(&relative))?)?;
+        }
+    }
+
+    Ok(zip.finish().map_err(zip_error)?.into_inner())
+}
+
+/// Files below a config folder as `/`-separated relative paths, sorted.
+pub fn list_configs(config_dir: &Path) -> Result<Vec<String>> {
+    if !config_dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = Vec::new();
+    for entry in WalkDir::new(config_dir).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(config_dir).map_err(|e| AppError::Custom(e.to_string()))?;
+        files.push(relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"));
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn zip_error(e: zip::result::ZipError) -> AppError {
+    AppError::Custom(format!("Bundle archive error: {}", e))
+}
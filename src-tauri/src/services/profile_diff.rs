@@ -0,0 +1,171 @@
+use crate::error::{AppError, Result};
+use crate::models::{ConfigChange, ConfigKey, PackageChange, PackageChangeKind};
+use crate::services::config_manager;
+use crate::services::profile_bundle;
+use crate::services::profile_history;
+use crate::services::profile_import::ImportEntry;
+use crate::utils::{file_ops, version};
+use rusqlite::Connection;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageState {
+    pub mod_id: String,
+    pub version: String,
+    pub enabled: bool,
+}
+
+/// A profile's mods keyed by package name ("Team-Name").
+pub type PackageSet = BTreeMap<String, PackageState>;
+
+pub fn profile_packages(conn: &Connection, profile_id: &str) -> Result<PackageSet> {
+    let mut stmt = conn.prepare("SELECT mod_id, version, enabled FROM profile_mods WHERE profile_id = ?1")?;
+    let rows = stmt.query_map([profile_id], |row| {
+        Ok(PackageState { mod_id: row.get(0)?, version: row.get(1)?, enabled: row.get(2)? })
+    })?;
+
+    let mut packages = PackageSet::new();
+    for row in rows {
+        let state = row?;
+        packages.insert(package_name(&state.mod_id).to_string(), state);
+    }
+    Ok(packages)
+}
+
+/// Packages of an imported profile; bare package names get an empty version.
+pub fn entry_packages(entries: &[ImportEntry]) -> PackageSet {
+    entries
+        .iter()
+        .map(|entry| {
+            let ver = version::split_package_id(&entry.name).map_or("", |(_, v)| v);
+            let state = PackageState { mod_id: entry.name.clone(), version: ver.to_string(), enabled: entry.enabled };
+            (package_name(&entry.name).to_string(), state)
+        })
+        .collect()
+}
+
+pub fn diff_packages(left: &PackageSet, right: &PackageSet) -> Vec<PackageChange> {
+    let names: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let (l, r) = (left.get(name), right.get(name));
+            let kind = match (l, r) {
+                (None, Some(_)) => PackageChangeKind::Added,
+                (Some(_), None) => PackageChangeKind::Removed,
+                (Some(l), Some(r)) if l.version != r.version => PackageChangeKind::VersionChanged,
+                (Some(l), Some(r)) if l.enabled != r.enabled => PackageChangeKind::EnabledChanged,
+                _ => return None,
+            };
+            Some(PackageChange {
+                package_name: name.clone(),
+                kind,
+                left_version: l.map(|s| s.version.clone()),
+                right_version: r.map(|s| s.version.clone()),
+                left_enabled: l.map(|s| s.enabled),
+                right_enabled: r.map(|s| s.enabled),
+            })
+        })
+        .collect()
+}
+
+/// Compares the `.cfg` files of two config folders value by value.
+pub fn diff_configs(left_dir: &Path, right_dir: &Path) -> Result<Vec<ConfigChange>> {
+    if left_dir == right_dir {
+        return Ok(vec![]);
+    }
+
+    let files: BTreeSet<String> = profile_bundle::list_configs(left_dir)?
+        .into_iter()
+        .chain(profile_bundle::list_configs(right_dir)?)
+        .filter(|file| file.ends_with(".cfg"))
+        .collect();
+
+    let mut changes = Vec::new();
+    for file in files {
+        let left = read_cfg(left_dir, &file)?;
+        let right = read_cfg(right_dir, &file)?;
+        let keys: BTreeSet<&(String, String)> = left.keys().chain(right.keys()).collect();
+        for key in keys {
+            let (l, r) = (left.get(key), right.get(key));
+            if l != r {
+                changes.push(ConfigChange {
+                    file: file.clone(),
+                    section: key.0.clone(),
+                    key: key.1.clone(),
+                    left_value: l.cloned(),
+                    right_value: r.cloned(),
+                });
+            }
+        }
+    }
+    Ok(changes)
+}
+
+/// Gives `target_id` the source's state of each selected package: added, re-pinned,
+/// toggled or removed.
+pub fn merge_packages(conn: &mut Connection, target_id: &str, source: &PackageSet, selected: &[String]) -> Result<()> {
+    let target = profile_packages(conn, target_id)?;
+    let tx = conn.transaction()?;
+    let before = profile_history::snapshot(&tx, target_id)?;
+    for name in selected {
+        if let Some(current) = target.get(name) {
+            tx.execute("DELETE FROM profile_mods WHERE profile_id = ?1 AND mod_id = ?2", (target_id, &current.mod_id))?;
+        }
+        if let Some(state) = source.get(name) {
+            tx.execute(
+                "INSERT INTO profile_mods (profile_id, mod_id, enabled, version) VALUES (?1, ?2, ?3, ?4)",
+                (target_id, &state.mod_id, state.enabled, &state.version),
+            )?;
+        }
+    }
+    profile_history::record(&tx, target_id, "Merged packages", &before)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Copies the selected values from the source config folder into the target one, editing
+/// the target files in place.
+pub fn merge_configs(source_dir: &Path, target_dir: &Path, selected: &[ConfigKey]) -> Result<()> {
+    let mut by_file: BTreeMap<&str, Vec<&ConfigKey>> = BTreeMap::new();
+    for key in selected {
+        by_file.entry(key.file.as_str()).or_default().push(key);
+    }
+
+    for (file, keys) in by_file {
+        let relative = file_ops::safe_relative_path(file)
+            .ok_or_else(|| AppError::Validation(format!("'{}' is not a config file path", file)))?;
+        let source = read_cfg(source_dir, file)?;
+        let target_path = target_dir.join(relative);
+        let mut content = read_existing(&target_path)?;
+        for key in keys {
+            let value = source.get(&(key.section.clone(), key.key.clone()));
+            content = config_manager::set_value(&content, &key.section, &key.key, value.map(String::as_str));
+        }
+        if let Some(parent) = target_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target_path, content)?;
+    }
+    Ok(())
+}
+
+fn read_cfg(dir: &Path, file: &str) -> Result<BTreeMap<(String, String), String>> {
+    Ok(config_manager::parse_cfg(&read_existing(&dir.join(file))?))
+}
+
+/// Reads a config file, treating one that does not exist as empty. Any other error is
+/// returned, since merging into a file that could not be read would drop its other values.
+fn read_existing(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(AppError::Custom(format!("Could not read {}: {}", path.display(), e))),
+    }
+}
+
+fn package_name(mod_id: &str) -> &str {
+    version::split_package_id(mod_id).map_or(mod_id, |(package, _)| package)
+}
@@ -0,0 +1,262 @@
+use crate::commands::mod_operations;
+use crate::error::Result;
+use crate::models::{HealthFinding, HealthFindingKind, HealthSeverity, ProfileHealth};
+use crate::utils::version;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// Package every Valheim mod depends on for BepInEx itself; it is installed into the game
+/// folder rather than the repository.
+const BEPINEX_PACK: &str = "denikson-BepInExPack_Valheim";
+/// BepInEx writes its version into the first line of this log at every start.
+const LOG_FILE: &str = "LogOutput.log";
+
+/// Folders a profile is checked against. Each check that needs a folder is skipped when
+/// it is `None`.
+pub struct HealthTarget<'a> {
+    pub repository_path: &'a Path,
+    /// Plugins folder the profile's mods are linked into, when they currently are.
+    pub plugins_dir: Option<&'a Path>,
+    /// BepInEx root the profile runs with.
+    pub bepinex_root: Option<&'a Path>,
+}
+
+struct Entry {
+    mod_id: String,
+    package: String,
+    version: String,
+    enabled: bool,
+}
+
+/// Checks that a profile can be launched as it is.
+pub fn check(conn: &Connection, profile_id: &str, target: &HealthTarget) -> Result<ProfileHealth> {
+    let mut stmt = conn.prepare("SELECT mod_id, version, enabled FROM profile_mods WHERE profile_id = ?1 ORDER BY mod_id")?;
+    let entries: Vec<Entry> = stmt
+        .query_map([profile_id], |row| {
+            let mod_id: String = row.get(0)?;
+            let package = version::split_package_id(&mod_id).map_or(mod_id.as_str(), |(p, _)| p).to_string();
+            Ok(Entry { mod_id, package, version: row.get(1)?, enabled: row.get(2)? })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut findings = Vec::new();
+    check_duplicates(&entries, &mut findings);
+    for entry in entries.iter().filter(|e| e.enabled) {
+        check_repository(entry, target.repository_path, &mut findings);
+        check_deprecated(conn, entry, &mut findings)?;
+    }
+    let required_bepinex = check_dependencies(conn, &entries, target.repository_path, &mut findings)?;
+    if let Some(plugins_dir) = target.plugins_dir {
+        check_links(plugins_dir, &mut findings)?;
+    }
+    check_bepinex(target.bepinex_root, required_bepinex, &mut findings);
+
+    findings.sort_by_key(|f| f.severity);
+    let launchable = !findings.iter().any(|f| f.severity == HealthSeverity::Error);
+    Ok(ProfileHealth { profile_id: profile_id.to_string(), launchable, findings })
+}
+
+fn check_duplicates(entries: &[Entry], findings: &mut Vec<HealthFinding>) {
+    let mut by_package: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+    for entry in entries {
+        by_package.entry(&entry.package).or_default().push(entry);
+    }
+
+    for (package, versions) in by_package.into_iter().filter(|(_, v)| v.len() > 1) {
+        let enabled = versions.iter().filter(|e| e.enabled).count();
+        let newest = versions
+            .iter()
+            .max_by(|a, b| version::parse(&a.version).cmp(&version::parse(&b.version)))
+            .map(|e| e.version.as_str())
+            .unwrap_or_default();
+        let listed: Vec<&str> = versions.iter().map(|e| e.version.as_str()).collect();
+        findings.push(HealthFinding {
+            kind: HealthFindingKind::DuplicatePackage,
+            // Two loaded copies of the same plugin make BepInEx skip one of them at random
+            severity: if enabled > 1 { HealthSeverity::Error } else { HealthSeverity::Warning },
+            subject: Some(package.to_string()),
+            message: format!("{} is in the profile in versions {}", package, listed.join(", ")),
+            suggested_fix: format!("Keep only one version of {}, e.g. {}", package, newest),
+        });
+    }
+}
+
+fn check_repository(entry: &Entry, repository_path: &Path, findings: &mut Vec<HealthFinding>) {
+    if repository_path.join(&entry.mod_id).is_dir() {
+        return;
+    }
+    findings.push(HealthFinding {
+        kind: HealthFindingKind::NotInRepository,
+        severity: HealthSeverity::Error,
+        subject: Some(entry.mod_id.clone()),
+        message: format!("{} is not in the repository and will not be loaded", entry.mod_id),
+        suggested_fix: format!("Install {} or remove it from the profile", entry.mod_id),
+    });
+}
+
+fn check_deprecated(conn: &Connection, entry: &Entry, findings: &mut Vec<HealthFinding>) -> Result<()> {
+    let deprecated: Option<bool> = conn
+        .query_row("SELECT is_deprecated FROM mods WHERE id = ?1", [&entry.package], |row| row.get(0))
+        .optional()?;
+    if deprecated == Some(true) {
+        findings.push(HealthFinding {
+            kind: HealthFindingKind::DeprecatedPackage,
+            severity: HealthSeverity::Warning,
+            subject: Some(entry.mod_id.clone()),
+            message: format!("{} is deprecated on Thunderstore and may no longer work", entry.package),
+            suggested_fix: format!("Look for a maintained replacement of {}", entry.package),
+        });
+    }
+    Ok(())
+}
+
+/// Reports dependencies of enabled mods that the profile lacks, has disabled or has in an
+/// older version. Returns the newest BepInEx pack version any of them requires.
+fn check_dependencies(conn: &Connection, entries: &[Entry], repository_path: &Path, findings: &mut Vec<HealthFinding>) -> Result<Option<String>> {
+    let mut required_bepinex: Option<String> = None;
+    let mut reported = BTreeSet::new();
+
+    for entry in entries.iter().filter(|e| e.enabled) {
+        for dependency in dependencies(conn, &entry.mod_id, repository_path)? {
+            let Some((package, required)) = version::split_package_id(&dependency) else {
+                continue;
+            };
+            if package == BEPINEX_PACK {
+                if required_bepinex.as_deref().is_none_or(|current| version::is_newer(required, current)) {
+                    required_bepinex = Some(required.to_string());
+                }
+                continue;
+            }
+
+            let present: Vec<&Entry> = entries.iter().filter(|e| e.package == package).collect();
+            let problem = match present.iter().find(|e| e.enabled) {
+                None if present.is_empty() => Some((
+                    format!("{} requires {}, which is not in the profile", entry.mod_id, package),
+                    format!("Add {} to the profile", dependency),
+                )),
+                None => Some((
+                    format!("{} requires {}, which is disabled", entry.mod_id, package),
+                    format!("Enable {}", present[0].mod_id),
+                )),
+                Some(found) if version::is_newer(required, &found.version) && version::parse(&found.version).is_some() => Some((
+                    format!("{} requires {} {} or newer, the profile has {}", entry.mod_id, package, required, found.version),
+                    format!("Update {} to {} or newer", package, required),
+                )),
+                Some(_) => None,
+            };
+
+            if let Some((message, suggested_fix)) = problem {
+                if reported.insert((entry.mod_id.clone(), package.to_string())) {
+                    findings.push(HealthFinding {
+                        kind: HealthFindingKind::MissingDependency,
+                        severity: HealthSeverity::Error,
+                        subject: Some(entry.mod_id.clone()),
+                        message,
+                        suggested_fix,
+                    });
+                }
+            }
+        }
+    }
+    Ok(required_bepinex)
+}
+
+/// Dependencies from the catalog, or the installed manifest for packages it does not know.
+fn dependencies(conn: &Connection, mod_id: &str, repository_path: &Path) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT dependency_id FROM mod_dependencies WHERE version_full_name = ?1")?;
+    let deps: Vec<String> = stmt.query_map([mod_id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+    if !deps.is_empty() {
+        return Ok(deps);
+    }
+    Ok(mod_operations::read_manifest(&repository_path.join(mod_id))
+        .and_then(|m| m.dependencies)
+        .unwrap_or_default())
+}
+
+fn check_links(plugins_dir: &Path, findings: &mut Vec<HealthFinding>) -> Result<()> {
+    if !plugins_dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(plugins_dir)? {
+        let path = entry?.path();
+        let is_link = path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink());
+        if !is_link || path.exists() {
+            continue;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let target = fs::read_link(&path).map(|t| t.display().to_string()).unwrap_or_default();
+        findings.push(HealthFinding {
+            kind: HealthFindingKind::BrokenLink,
+            severity: HealthSeverity::Error,
+            subject: Some(name.clone()),
+            message: format!("{} in the plugins folder points to {}, which does not exist", name, target),
+            suggested_fix: "Switch to the profile again to re-link its mods".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn check_bepinex(root: Option<&Path>, required: Option<String>, findings: &mut Vec<HealthFinding>) {
+    let Some(root) = root else {
+        findings.push(HealthFinding {
+            kind: HealthFindingKind::BepInExVersion,
+            severity: HealthSeverity::Info,
+            subject: None,
+            message: "The game folder is not configured, BepInEx could not be checked".to_string(),
+            suggested_fix: "Set the Valheim path in the settings".to_string(),
+        });
+        return;
+    };
+
+    let pack = required.as_deref().map(|v| format!("{}-{}", BEPINEX_PACK, v)).unwrap_or_else(|| BEPINEX_PACK.to_string());
+    if !root.join("core").join("BepInEx.dll").exists() {
+        findings.push(HealthFinding {
+            kind: HealthFindingKind::BepInExVersion,
+            severity: HealthSeverity::Error,
+            subject: None,
+            message: format!("BepInEx is not installed in {}", root.display()),
+            suggested_fix: format!("Install {} into the game folder", pack),
+        });
+        return;
+    }
+
+    let Some(required) = required else {
+        return;
+    };
+    match installed_version(root) {
+        // Pack versions encode the BepInEx version as major.minor.<patch><build>
+        // ("5.4.2202" ships BepInEx 5.4.22), so only major and minor are comparable
+        Some(installed) => {
+            let below = version::parse(&required)
+                .zip(version::parse(&installed))
+                .is_some_and(|(r, i)| (i.major, i.minor) < (r.major, r.minor));
+            if below {
+                findings.push(HealthFinding {
+                    kind: HealthFindingKind::BepInExVersion,
+                    severity: HealthSeverity::Warning,
+                    subject: None,
+                    message: format!("Mods require {}, but BepInEx {} is installed", pack, installed),
+                    suggested_fix: format!("Update BepInEx to {}", pack),
+                });
+            }
+        }
+        None => findings.push(HealthFinding {
+            kind: HealthFindingKind::BepInExVersion,
+            severity: HealthSeverity::Info,
+            subject: None,
+            message: format!("Mods require {}; the installed BepInEx version is unknown until the game has run once", pack),
+            suggested_fix: "Start the game once, then validate again".to_string(),
+        }),
+    }
+}
+
+/// BepInEx version from the first line of its log, "[Message:   BepInEx] BepInEx 5.4.22.0 - valheim".
+fn installed_version(root: &Path) -> Option<String> {
+    let log = fs::read_to_string(root.join(LOG_FILE)).ok()?;
+    let line = log.lines().find(|l| l.contains("] BepInEx "))?;
+    let ver = line.split("] BepInEx ").nth(1)?.split_whitespace().next()?;
+    let parts: Vec<&str> = ver.split('.').take(3).collect();
+    (parts.len() == 3).then(|| parts.join("."))
+}
@@ -0,0 +1,195 @@
+use crate::error::{AppError, Result};
+use crate::models::ProfileHistoryEntry;
+use crate::utils::version;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A profile mod as stored in history snapshots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryMod {
+    pub mod_id: String,
+    pub enabled: bool,
+    pub version: String,
+}
+
+/// The current mod list of a profile, ordered by mod id.
+pub fn snapshot(conn: &Connection, profile_id: &str) -> Result<Vec<HistoryMod>> {
+    let mut stmt = conn.prepare("SELECT mod_id, enabled, version FROM profile_mods WHERE profile_id = ?1 ORDER BY mod_id")?;
+    let mods = stmt
+        .query_map([profile_id], |row| Ok(HistoryMod { mod_id: row.get(0)?, enabled: row.get(1)?, version: row.get(2)? }))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(mods)
+}
+
+/// Records a change if the mod list now differs from `before`. Call it on the same
+/// connection or transaction that made the change.
+pub fn record(conn: &Connection, profile_id: &str, action: &str, before: &[HistoryMod]) -> Result<()> {
+    insert(conn, profile_id, action, before, None)
+}
+
+/// Writes a history entry. An undo is recorded even when it changed nothing, since its
+/// entry is what marks the undone change as such.
+fn insert(conn: &Connection, profile_id: &str, action: &str, before: &[HistoryMod], undoes: Option<i64>) -> Result<()> {
+    let after = snapshot(conn, profile_id)?;
+    if after == before && undoes.is_none() {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO profile_history (profile_id, changed_at, action, mods_before, mods_after, undoes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        (
+            profile_id,
+            chrono::Utc::now().to_rfc3339(),
+            action,
+            serde_json::to_string(before)?,
+            serde_json::to_string(&after)?,
+            undoes,
+        ),
+    )?;
+    Ok(())
+}
+
+/// History of a profile, newest first.
+pub fn list(conn: &Connection, profile_id: &str) -> Result<Vec<ProfileHistoryEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, changed_at, action, mods_before, mods_after FROM profile_history WHERE profile_id = ?1 ORDER BY id DESC",
+    )?;
+    let rows = stmt.query_map([profile_id], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?))
+    })?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (id, changed_at, action, before, after) = row?;
+        let before: Vec<HistoryMod> = serde_json::from_str(&before)?;
+        let after: Vec<HistoryMod> = serde_json::from_str(&after)?;
+        let (added, removed, changed) = changes(&before, &after);
+        entries.push(ProfileHistoryEntry { id, profile_id: profile_id.to_string(), changed_at, action, added, removed, changed });
+    }
+    Ok(entries)
+}
+
+/// Mod list right after the given history entry, or right before it with `before`.
+pub fn state_at(conn: &Connection, profile_id: &str, history_id: i64, before: bool) -> Result<Vec<HistoryMod>> {
+    let column = if before { "mods_before" } else { "mods_after" };
+    let json: String = conn
+        .query_row(
+            &format!("SELECT {} FROM profile_history WHERE id = ?1 AND profile_id = ?2", column),
+            (history_id, profile_id),
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::Custom(format!("History entry {} not found for profile {}", history_id, profile_id)))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// Id of the most recent change of a profile that undo can still reverse: undos themselves
+/// and the changes they undid are skipped, so repeated undos walk back through the history.
+pub fn latest_undoable(conn: &Connection, profile_id: &str) -> Result<Option<i64>> {
+    Ok(conn
+        .query_row(
+            "SELECT MAX(id) FROM profile_history
+             WHERE profile_id = ?1 AND undoes IS NULL
+               AND id NOT IN (SELECT undoes FROM profile_history WHERE profile_id = ?1 AND undoes IS NOT NULL)",
+            [profile_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten())
+}
+
+/// Replaces a profile's mod list with a snapshot and records it as a change.
+pub fn restore(conn: &mut Connection, profile_id: &str, mods: &[HistoryMod], action: &str) -> Result<()> {
+    replace(conn, profile_id, mods, action, None)
+}
+
+/// Like `restore`, for undoing the given entry: `mods` is the list from before it. The
+/// entry is marked as undone, so later undos skip it.
+pub fn restore_undo(conn: &mut Connection, profile_id: &str, mods: &[HistoryMod], history_id: i64) -> Result<()> {
+    replace(conn, profile_id, mods, &format!("Undid #{}", history_id), Some(history_id))
+}
+
+fn replace(conn: &mut Connection, profile_id: &str, mods: &[HistoryMod], action: &str, undoes: Option<i64>) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = snapshot(&tx, profile_id)?;
+    tx.execute("DELETE FROM profile_mods WHERE profile_id = ?1", [profile_id])?;
+    for m in mods {
+        tx.execute(
+            "INSERT INTO profile_mods (profile_id, mod_id, enabled, version) VALUES (?1, ?2, ?3, ?4)",
+            (profile_id, &m.mod_id, m.enabled, &m.version),
+        )?;
+    }
+    insert(&tx, profile_id, action, &before, undoes)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Added, removed and changed mods between two snapshots, compared by package.
+fn changes(before: &[HistoryMod], after: &[HistoryMod]) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let by_package = |mods: &[HistoryMod]| -> BTreeMap<String, HistoryMod> {
+        mods.iter()
+            .map(|m| (version::split_package_id(&m.mod_id).map_or(m.mod_id.as_str(), |(p, _)| p).to_string(), m.clone()))
+            .collect()
+    };
+    let (before, after) = (by_package(before), by_package(after));
+
+    let added = after.iter().filter(|(p, _)| !before.contains_key(*p)).map(|(_, m)| m.mod_id.clone()).collect();
+    let removed = before.iter().filter(|(p, _)| !after.contains_key(*p)).map(|(_, m)| m.mod_id.clone()).collect();
+    let changed = after
+        .iter()
+        .filter(|(p, m)| before.get(*p).is_some_and(|old| old != *m))
+        .map(|(_, m)| m.mod_id.clone())
+        .collect();
+    (added, removed, changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_mod(conn: &Connection, mod_id: &str) -> i64 {
+        let before = snapshot(conn, "p").unwrap();
+        conn.execute(
+            "INSERT INTO profile_mods (profile_id, mod_id, enabled, version) VALUES ('p', ?1, 1, '1.0.0')",
+            [mod_id],
+        )
+        .unwrap();
+        record(conn, "p", &format!("Added {}", mod_id), &before).unwrap();
+        conn.last_insert_rowid()
+    }
+
+    fn undo(conn: &mut Connection) -> i64 {
+        let history_id = latest_undoable(conn, "p").unwrap().expect("something to undo");
+        let mods = state_at(conn, "p", history_id, true).unwrap();
+        restore_undo(conn, "p", &mods, history_id).unwrap();
+        history_id
+    }
+
+    fn mod_ids(conn: &Connection) -> Vec<String> {
+        snapshot(conn, "p").unwrap().into_iter().map(|m| m.mod_id).collect()
+    }
+
+    #[test]
+    fn repeated_undo_steps_further_back() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::schema::create_tables(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO profiles (id, name, description, icon, color, created, last_used) VALUES ('p', 'P', '', '', '', '', '')",
+            [],
+        )
+        .unwrap();
+
+        let first = add_mod(&conn, "Team-A-1.0.0");
+        let second = add_mod(&conn, "Team-B-1.0.0");
+
+        assert_eq!(undo(&mut conn), second);
+        assert_eq!(mod_ids(&conn), ["Team-A-1.0.0"]);
+
+        assert_eq!(undo(&mut conn), first);
+        assert!(mod_ids(&conn).is_empty());
+
+        assert_eq!(latest_undoable(&conn, "p").unwrap(), None);
+    }
+}
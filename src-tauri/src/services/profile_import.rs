@@ -0,0 +1,110 @@
+use crate::error::Result;
+use crate::models::{ImportPlan, PlannedMod, UnresolvedMod};
+use crate::utils::version;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// A mod listed by an imported profile: a versioned id ("Team-Name-1.0.0") or a bare
+/// package name, which resolves to the newest version in the catalog.
+#[derive(Debug, Clone)]
+pub struct ImportEntry {
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// Resolves imported entries against the catalog and the repository. Dependencies are
+/// added as enabled entries unless the import already lists a version of that package.
+pub fn resolve(conn: &Connection, entries: &[ImportEntry], repository_path: &Path) -> Result<ImportPlan> {
+    let mut plan = ImportPlan::default();
+    let mut packages = HashSet::new();
+    let mut queue = Vec::new();
+
+    for entry in entries {
+        match resolve_one(conn, &entry.name, repository_path)? {
+            Ok(mut planned) => {
+                if !packages.insert(planned.package_name.clone()) {
+                    plan.unresolved.push(UnresolvedMod {
+                        name: entry.name.clone(),
+                        reason: format!("{} is listed more than once", planned.package_name),
+                    });
+                    continue;
+                }
+                planned.enabled = entry.enabled;
+                queue.push(planned.mod_id.clone());
+                plan.entries.push(planned);
+            }
+            Err(reason) => plan.unresolved.push(UnresolvedMod { name: entry.name.clone(), reason }),
+        }
+    }
+
+    while let Some(mod_id) = queue.pop() {
+        for dependency in dependencies(conn, &mod_id)? {
+            let package = version::split_package_id(&dependency).map_or(dependency.as_str(), |(p, _)| p);
+            // BepInEx itself is managed separately and never goes into the repository
+            if package.starts_with("denikson-BepInExPack") || packages.contains(package) {
+                continue;
+            }
+            match resolve_one(conn, &dependency, repository_path)? {
+                Ok(mut planned) => {
+                    planned.dependency = true;
+                    packages.insert(planned.package_name.clone());
+                    queue.push(planned.mod_id.clone());
+                    plan.entries.push(planned);
+                }
+                Err(reason) => plan.unresolved.push(UnresolvedMod {
+                    name: dependency.clone(),
+                    reason: format!("Dependency of {}: {}", mod_id, reason),
+                }),
+            }
+        }
+    }
+
+    Ok(plan)
+}
+
+/// The inner `Err` is a user-facing reason the entry could not be resolved.
+fn resolve_one(conn: &Connection, name: &str, repository_path: &Path) -> Result<std::result::Result<PlannedMod, String>> {
+    let name = name.trim();
+    let (package, ver) = match version::split_package_id(name) {
+        Some((package, ver)) => (package.to_string(), ver.to_string()),
+        None => match latest_version(conn, name)? {
+            Some(ver) => (name.to_string(), ver),
+            None => return Ok(Err("Not found in the catalog".to_string())),
+        },
+    };
+    if package.split_once('-').is_none_or(|(team, n)| team.is_empty() || n.is_empty()) {
+        return Ok(Err("Not a Thunderstore package name".to_string()));
+    }
+
+    let mod_id = format!("{}-{}", package, ver);
+    let installed = repository_path.join(&mod_id).is_dir();
+    let download_url: Option<String> = conn
+        .query_row("SELECT download_url FROM mod_versions WHERE full_name = ?1", [&mod_id], |row| row.get(0))
+        .optional()?;
+    if download_url.is_none() && !installed {
+        return Ok(Err(format!("Version {} of {} is not in the catalog", ver, package)));
+    }
+
+    Ok(Ok(PlannedMod {
+        mod_id,
+        package_name: package,
+        version: ver,
+        enabled: true,
+        download_url: download_url.unwrap_or_default(),
+        installed,
+        dependency: false,
+    }))
+}
+
+fn latest_version(conn: &Connection, package: &str) -> Result<Option<String>> {
+    let mut stmt = conn.prepare("SELECT version_number FROM mod_versions WHERE mod_id = ?1")?;
+    let versions: Vec<String> = stmt.query_map([package], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+    Ok(versions.into_iter().filter_map(|v| version::parse(&v).map(|parsed| (parsed, v))).max().map(|(_, v)| v))
+}
+
+fn dependencies(conn: &Connection, mod_id: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT dependency_id FROM mod_dependencies WHERE version_full_name = ?1 ORDER BY dependency_id")?;
+    let deps = stmt.query_map([mod_id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+    Ok(deps)
+}
@@ -0,0 +1,183 @@
+use crate::error::{AppError, Result};
+use crate::models::{LockMismatch, LockMismatchKind, LockedPackage, ProfileLock};
+use crate::services::{profile_history, profile_manager};
+use crate::utils::{hash, version};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const LOCK_VERSION: u32 = 1;
+const LOCK_FILE: &str = "profile.lock.json";
+
+/// A profile entry with what the database knows about where it came from.
+struct LockSource {
+    mod_id: String,
+    version: String,
+    enabled: bool,
+    download_url: String,
+    sha256: String,
+}
+
+/// Lockfile of a profile: `<app data>/profiles/<id>/profile.lock.json`.
+pub fn lock_path(app_data_dir: &Path, profile_id: &str) -> PathBuf {
+    app_data_dir.join("profiles").join(profile_id).join(LOCK_FILE)
+}
+
+pub fn read(path: &Path) -> Result<Option<ProfileLock>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let lock: ProfileLock = serde_json::from_str(&fs::read_to_string(path)?)?;
+    if lock.lock_version > LOCK_VERSION {
+        return Err(AppError::Custom(format!(
+            "Lockfile version {} is newer than supported ({})",
+            lock.lock_version, LOCK_VERSION
+        )));
+    }
+    Ok(Some(lock))
+}
+
+/// Writes through a temporary file so an interrupted write never leaves a truncated lock.
+pub fn write(path: &Path, lock: &ProfileLock) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(lock)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Pins every mod of a profile to its installed files. Archive hashes recorded at install
+/// time are reused; for older installs the archive is downloaded once to hash it.
+pub async fn build(db_conn: &Arc<Mutex<Connection>>, profile_id: &str, repository_path: &Path) -> Result<ProfileLock> {
+    let (profile_name, sources) = {
+        let conn = db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let profile = profile_manager::load_profile(&conn, profile_id)?;
+        (profile.name, lock_sources(&conn, profile_id)?)
+    };
+
+    let missing: Vec<&str> = sources
+        .iter()
+        .filter(|s| !repository_path.join(&s.mod_id).is_dir())
+        .map(|s| s.mod_id.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::Validation(format!("Not installed, cannot lock: {}", missing.join(", "))));
+    }
+
+    let mut packages = Vec::new();
+    for source in sources {
+        let mut sha256 = source.sha256;
+        if sha256.is_empty() && !source.download_url.is_empty() {
+            tracing::info!("Hashing archive of {} for the lockfile", source.mod_id);
+            let content = reqwest::get(&source.download_url).await?.error_for_status()?.bytes().await?;
+            sha256 = hash::sha256_hex(&content);
+
+            let conn = db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+            conn.execute(
+                "INSERT OR REPLACE INTO package_archives (mod_id, download_url, sha256, installed_at) VALUES (?1, ?2, ?3, ?4)",
+                (&source.mod_id, &source.download_url, &sha256, chrono::Utc::now().to_rfc3339()),
+            )?;
+        }
+
+        packages.push(LockedPackage {
+            package_name: package_name(&source.mod_id).to_string(),
+            content_sha256: hash::hash_dir(&repository_path.join(&source.mod_id))?,
+            mod_id: source.mod_id,
+            version: source.version,
+            enabled: source.enabled,
+            download_url: source.download_url,
+            sha256,
+        });
+    }
+
+    Ok(ProfileLock {
+        lock_version: LOCK_VERSION,
+        profile_name,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        packages,
+    })
+}
+
+/// Compares a lock with the profile's current mod list and the files in the repository.
+pub fn check(conn: &Connection, profile_id: &str, lock: &ProfileLock, repository_path: &Path) -> Result<Vec<LockMismatch>> {
+    let mut current: HashMap<String, String> = lock_sources(conn, profile_id)?
+        .into_iter()
+        .map(|s| (package_name(&s.mod_id).to_string(), s.mod_id))
+        .collect();
+
+    let mut mismatches = Vec::new();
+    for package in &lock.packages {
+        let kind = match current.remove(&package.package_name) {
+            None => Some(LockMismatchKind::Removed),
+            Some(mod_id) if mod_id != package.mod_id => Some(LockMismatchKind::VersionChanged),
+            Some(_) => files_mismatch(package, repository_path)?,
+        };
+        if let Some(kind) = kind {
+            mismatches.push(LockMismatch { mod_id: package.mod_id.clone(), kind });
+        }
+    }
+
+    let mut unlocked: Vec<String> = current.into_values().collect();
+    unlocked.sort();
+    mismatches.extend(unlocked.into_iter().map(|mod_id| LockMismatch { mod_id, kind: LockMismatchKind::Unlocked }));
+    Ok(mismatches)
+}
+
+/// `Missing` or `Modified` if the repository copy of a locked package does not match.
+pub fn files_mismatch(package: &LockedPackage, repository_path: &Path) -> Result<Option<LockMismatchKind>> {
+    let dir = repository_path.join(&package.mod_id);
+    if !dir.is_dir() {
+        return Ok(Some(LockMismatchKind::Missing));
+    }
+    if hash::hash_dir(&dir)? != package.content_sha256 {
+        return Ok(Some(LockMismatchKind::Modified));
+    }
+    Ok(None)
+}
+
+/// Replaces a profile's mod list with the locked packages.
+pub fn apply_to_profile(conn: &mut Connection, profile_id: &str, lock: &ProfileLock) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = profile_history::snapshot(&tx, profile_id)?;
+    tx.execute("DELETE FROM profile_mods WHERE profile_id = ?1", [profile_id])?;
+    for package in &lock.packages {
+        tx.execute(
+            "INSERT INTO profile_mods (profile_id, mod_id, enabled, version) VALUES (?1, ?2, ?3, ?4)",
+            (profile_id, &package.mod_id, package.enabled, &package.version),
+        )?;
+    }
+    profile_history::record(&tx, profile_id, "Synced with lockfile", &before)?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn lock_sources(conn: &Connection, profile_id: &str) -> Result<Vec<LockSource>> {
+    let mut stmt = conn.prepare(
+        "SELECT pm.mod_id, pm.version, pm.enabled, COALESCE(pa.download_url, mv.download_url, ''), COALESCE(pa.sha256, '')
+         FROM profile_mods pm
+         LEFT JOIN package_archives pa ON pa.mod_id = pm.mod_id
+         LEFT JOIN mod_versions mv ON mv.full_name = pm.mod_id
+         WHERE pm.profile_id = ?1
+         ORDER BY pm.mod_id",
+    )?;
+    let sources = stmt
+        .query_map([profile_id], |row| {
+            Ok(LockSource {
+                mod_id: row.get(0)?,
+                version: row.get(1)?,
+                enabled: row.get(2)?,
+                download_url: row.get(3)?,
+                sha256: row.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(sources)
+}
+
+fn package_name(mod_id: &str) -> &str {
+    version::split_package_id(mod_id).map_or(mod_id, |(package, _)| package)
+}
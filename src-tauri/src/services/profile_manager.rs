@@ -0,0 +1,557 @@
+use crate::error::{AppError, Result};
+use crate::models::{Profile, ProfileDetails, ProfileMod, ProfilePatch, ProfileSwitchResult, ProfileTemplate};
+use crate::services::profile_history;
+use crate::utils::{file_ops, validation, version};
+use rusqlite::{Connection, OptionalExtension, Row};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const PROFILE_COLUMNS: &str = "id, name, description, icon, color, active, created, last_used, play_time";
+
+/// Folders BepInEx resolves relative to its root; each isolated profile gets its own.
+const BEPINEX_SUBDIRS: &[&str] = &["config", "plugins", "patchers"];
+
+/// A filesystem step already applied during a switch, kept so it can be undone.
+enum LinkOp {
+    Linked(String),
+    Unlinked(String),
+}
+
+pub struct ProfileManager {
+    db_conn: Arc<Mutex<Connection>>,
+}
+
+impl ProfileManager {
+    pub fn new(db_conn: Arc<Mutex<Connection>>) -> Self {
+        Self { db_conn }
+    }
+
+    /// Makes the plugins folder match the enabled mods of `profile_id` and marks it active.
+    /// Only entries that differ are linked or unlinked; if any step fails, every step
+    /// already taken is reverted so the folder is left as it was.
+    pub fn switch(&self, profile_id: &str, repository_path: &Path, plugins_dir: &Path) -> Result<ProfileSwitchResult> {
+        let wanted = self.enabled_mods(profile_id)?;
+        fs::create_dir_all(plugins_dir)?;
+
+        let current = managed_entries(repository_path, plugins_dir)?;
+        let mut result = ProfileSwitchResult::default();
+        let mut to_link = Vec::new();
+        for mod_id in &wanted {
+            if !repository_path.join(mod_id).is_dir() {
+                tracing::warn!("Profile {} references {} which is not in the repository", profile_id, mod_id);
+                result.missing.push(mod_id.clone());
+            } else if !current.contains(mod_id) {
+                to_link.push(mod_id.clone());
+            }
+        }
+        let to_unlink: Vec<String> = current.iter().filter(|m| !wanted.contains(*m)).cloned().collect();
+
+        let mut applied = Vec::new();
+        let outcome = apply_ops(repository_path, plugins_dir, &to_unlink, &to_link, &mut applied)
+            .and_then(|_| self.set_active(profile_id));
+
+        if let Err(e) = outcome {
+            tracing::error!("Switching to profile {} failed, restoring plugins folder: {}", profile_id, e);
+            undo_ops(repository_path, plugins_dir, applied);
+            return Err(e);
+        }
+
+        result.linked = to_link;
+        result.unlinked = to_unlink;
+        Ok(result)
+    }
+
+    /// Validates and applies a patch in a single transaction, returning the updated profile.
+    /// All validation problems are reported together rather than one at a time.
+    pub fn update(&self, profile_id: &str, patch: ProfilePatch) -> Result<Profile> {
+        let mut conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let tx = conn.transaction()?;
+
+        let exists: bool = tx.query_row("SELECT EXISTS(SELECT 1 FROM profiles WHERE id = ?1)", [profile_id], |row| row.get(0))?;
+        if !exists {
+            return Err(AppError::ProfileNotFound(profile_id.to_string()));
+        }
+
+        let mut errors = Vec::new();
+
+        let name = match &patch.name {
+            Some(name) => match validate_name(&tx, name, Some(profile_id)) {
+                Ok(name) => Some(name),
+                Err(AppError::Validation(e)) => {
+                    errors.push(e);
+                    None
+                }
+                Err(e) => return Err(e),
+            },
+            None => None,
+        };
+
+        if let Some(color) = &patch.color {
+            if !validation::is_valid_color(color) {
+                errors.push(format!("'{}' is not a valid hex color", color));
+            }
+        }
+
+        let mut mods = Vec::new();
+        if let Some(entries) = &patch.mods {
+            let mut packages = HashSet::new();
+            for entry in entries {
+                let (package, ver) = match (&entry.version, version::split_package_id(&entry.mod_id)) {
+                    (Some(ver), Some((package, _))) => (package, ver.as_str()),
+                    (Some(ver), None) => (entry.mod_id.as_str(), ver.as_str()),
+                    (None, Some((package, ver))) => (package, ver),
+                    (None, None) => {
+                        errors.push(format!("{} has no version", entry.mod_id));
+                        continue;
+                    }
+                };
+                if version::parse(ver).is_none() {
+                    errors.push(format!("'{}' is not a valid version for {}", ver, package));
+                    continue;
+                }
+                if !packages.insert(package.to_string()) {
+                    errors.push(format!("{} is listed more than once", package));
+                    continue;
+                }
+
+                let mod_id = format!("{}-{}", package, ver);
+                let known: bool = tx.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM mod_versions WHERE full_name = ?1) OR EXISTS(SELECT 1 FROM mods WHERE id = ?1)",
+                    [&mod_id],
+                    |row| row.get(0),
+                )?;
+                if !known {
+                    errors.push(format!("Unknown package {}", mod_id));
+                    continue;
+                }
+                mods.push((mod_id, ver.to_string(), entry.enabled));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors.join("; ")));
+        }
+
+        tx.execute(
+            "UPDATE profiles SET
+                name = COALESCE(?2, name),
+                description = COALESCE(?3, description),
+                icon = COALESCE(?4, icon),
+                color = COALESCE(?5, color)
+             WHERE id = ?1",
+            (profile_id, name, &patch.description, &patch.icon, &patch.color),
+        )?;
+
+        if patch.mods.is_some() {
+            let before = profile_history::snapshot(&tx, profile_id)?;
+            tx.execute("DELETE FROM profile_mods WHERE profile_id = ?1", [profile_id])?;
+            for (mod_id, ver, enabled) in &mods {
+                tx.execute(
+                    "INSERT INTO profile_mods (profile_id, mod_id, enabled, version) VALUES (?1, ?2, ?3, ?4)",
+                    (profile_id, mod_id, enabled, ver),
+                )?;
+            }
+            profile_history::record(&tx, profile_id, "Edited mod list", &before)?;
+        }
+
+        tx.commit()?;
+        load_profile(&conn, profile_id)
+    }
+
+    /// Removes a profile row; its `profile_mods` follow through the cascading foreign key and
+    /// its update policies are removed alongside. The active profile is never deleted.
+    pub fn delete(&self, profile_id: &str) -> Result<()> {
+        let mut conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let tx = conn.transaction()?;
+
+        let active: Option<bool> = tx
+            .query_row("SELECT active FROM profiles WHERE id = ?1", [profile_id], |row| row.get(0))
+            .optional()?;
+        match active {
+            None => return Err(AppError::ProfileNotFound(profile_id.to_string())),
+            Some(true) => return Err(AppError::Validation("The active profile cannot be deleted".to_string())),
+            Some(false) => {}
+        }
+
+        tx.execute("DELETE FROM update_policies WHERE profile_id = ?1", [profile_id])?;
+        tx.execute("DELETE FROM profiles WHERE id = ?1", [profile_id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Creates an inactive copy of a profile, including each mod's enabled flag and version.
+    pub fn duplicate(&self, source_id: &str, name: &str) -> Result<Profile> {
+        let mut conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let tx = conn.transaction()?;
+
+        let source = load_profile(&tx, source_id)?;
+        let name = validate_name(&tx, name, None)?;
+        let id = insert_profile(&tx, &name, &source.description, &source.icon, &source.color)?;
+        tx.execute(
+            "INSERT INTO profile_mods (profile_id, mod_id, enabled, version)
+             SELECT ?1, mod_id, enabled, version FROM profile_mods WHERE profile_id = ?2",
+            (&id, source_id),
+        )?;
+        profile_history::record(&tx, &id, &format!("Duplicated from {}", source.name), &[])?;
+
+        tx.commit()?;
+        load_profile(&conn, &id)
+    }
+
+    /// Saves a profile's appearance and mod list as a template.
+    pub fn save_template(&self, profile_id: &str, name: &str) -> Result<ProfileTemplate> {
+        let mut conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let tx = conn.transaction()?;
+
+        let profile = load_profile(&tx, profile_id)?;
+        let name = validate_template_name(&tx, name)?;
+        let id = uuid::Uuid::new_v4().to_string();
+        tx.execute(
+            "INSERT INTO profile_templates (id, name, description, icon, color, created) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (&id, &name, &profile.description, &profile.icon, &profile.color, chrono::Utc::now().to_rfc3339()),
+        )?;
+        tx.execute(
+            "INSERT INTO profile_template_mods (template_id, mod_id, enabled, version)
+             SELECT ?1, mod_id, enabled, version FROM profile_mods WHERE profile_id = ?2",
+            (&id, profile_id),
+        )?;
+
+        tx.commit()?;
+        load_template(&conn, &id)
+    }
+
+    /// Creates an inactive profile with a template's appearance and mod list.
+    pub fn create_from_template(&self, template_id: &str, name: &str) -> Result<Profile> {
+        let mut conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let tx = conn.transaction()?;
+
+        let template = load_template(&tx, template_id)?;
+        let name = validate_name(&tx, name, None)?;
+        let id = insert_profile(&tx, &name, &template.description, &template.icon, &template.color)?;
+        tx.execute(
+            "INSERT INTO profile_mods (profile_id, mod_id, enabled, version)
+             SELECT ?1, mod_id, enabled, version FROM profile_template_mods WHERE template_id = ?2",
+            (&id, template_id),
+        )?;
+        profile_history::record(&tx, &id, &format!("Created from template {}", template.name), &[])?;
+
+        tx.commit()?;
+        load_profile(&conn, &id)
+    }
+
+    pub fn list_templates(&self) -> Result<Vec<ProfileTemplate>> {
+        let conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let ids: Vec<String> = conn
+            .prepare("SELECT id FROM profile_templates ORDER BY name COLLATE NOCASE")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        ids.iter().map(|id| load_template(&conn, id)).collect()
+    }
+
+    /// Removes a template; its mod list follows through the cascading foreign key.
+    pub fn delete_template(&self, template_id: &str) -> Result<()> {
+        let conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let removed = conn.execute("DELETE FROM profile_templates WHERE id = ?1", [template_id])?;
+        if removed == 0 {
+            return Err(AppError::Custom(format!("Template not found: {}", template_id)));
+        }
+        Ok(())
+    }
+
+    fn enabled_mods(&self, profile_id: &str) -> Result<BTreeSet<String>> {
+        let conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let exists: bool = conn.query_row("SELECT EXISTS(SELECT 1 FROM profiles WHERE id = ?1)", [profile_id], |row| row.get(0))?;
+        if !exists {
+            return Err(AppError::ProfileNotFound(profile_id.to_string()));
+        }
+
+        let mut stmt = conn.prepare("SELECT mod_id FROM profile_mods WHERE profile_id = ?1 AND enabled = 1")?;
+        let mods = stmt.query_map([profile_id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        Ok(mods)
+    }
+
+    fn set_active(&self, profile_id: &str) -> Result<()> {
+        let mut conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let tx = conn.transaction()?;
+        tx.execute("UPDATE profiles SET active = (id = ?1)", [profile_id])?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+pub fn profile_from_row(row: &Row) -> rusqlite::Result<Profile> {
+    Ok(Profile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        icon: row.get(3)?,
+        color: row.get(4)?,
+        active: row.get(5)?,
+        created: row.get(6)?,
+        last_used: row.get(7)?,
+        play_time: row.get(8)?,
+        mods: vec![],
+    })
+}
+
+/// Loads a profile together with the ids of its mods.
+pub fn load_profile(conn: &Connection, profile_id: &str) -> Result<Profile> {
+    let mut profile = conn
+        .query_row(
+            &format!("SELECT {} FROM profiles WHERE id = ?1", PROFILE_COLUMNS),
+            [profile_id],
+            profile_from_row,
+        )
+        .optional()?
+        .ok_or_else(|| AppError::ProfileNotFound(profile_id.to_string()))?;
+
+    let mut stmt = conn.prepare("SELECT mod_id FROM profile_mods WHERE profile_id = ?1 ORDER BY mod_id")?;
+    profile.mods = stmt.query_map([profile_id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+    Ok(profile)
+}
+
+/// Loads every profile with its mods, using one query for all mod entries. Entries are
+/// checked against `repository_path` to flag the ones that cannot be linked; without a
+/// configured repository nothing is flagged.
+pub fn load_profile_details(conn: &Connection, repository_path: Option<&Path>, profile_id: Option<&str>) -> Result<Vec<ProfileDetails>> {
+    let profiles: Vec<Profile> = match profile_id {
+        Some(id) => vec![load_profile(conn, id)?],
+        None => conn
+            .prepare(&format!("SELECT {} FROM profiles ORDER BY created", PROFILE_COLUMNS))?
+            .query_map([], profile_from_row)?
+            .collect::<rusqlite::Result<_>>()?,
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT pm.profile_id, pm.mod_id, pm.enabled, pm.version, mv.name, m.id IS NOT NULL
+         FROM profile_mods pm
+         LEFT JOIN mod_versions mv ON mv.full_name = pm.mod_id
+         LEFT JOIN mods m ON m.id = pm.mod_id
+         WHERE ?1 IS NULL OR pm.profile_id = ?1
+         ORDER BY pm.profile_id, pm.mod_id",
+    )?;
+    let rows = stmt.query_map([profile_id], |row| {
+        let profile_id: String = row.get(0)?;
+        let mod_id: String = row.get(1)?;
+        let name: Option<String> = row.get(4)?;
+        Ok((profile_id, mod_id, row.get::<_, bool>(2)?, row.get::<_, String>(3)?, name, row.get::<_, bool>(5)?))
+    })?;
+
+    let mut entries: HashMap<String, Vec<ProfileMod>> = HashMap::new();
+    for row in rows {
+        let (profile_id, mod_id, enabled, ver, name, installed) = row?;
+        let package_name = version::split_package_id(&mod_id).map_or(mod_id.as_str(), |(package, _)| package).to_string();
+        let name = name.unwrap_or_else(|| package_name.split_once('-').map_or(package_name.as_str(), |(_, n)| n).to_string());
+        let missing = repository_path.is_some_and(|repo| !repo.join(&mod_id).is_dir());
+        entries.entry(profile_id).or_default().push(ProfileMod {
+            mod_id,
+            package_name,
+            name,
+            version: ver,
+            enabled,
+            installed,
+            missing,
+        });
+    }
+
+    Ok(profiles
+        .into_iter()
+        .map(|mut profile| {
+            let mod_entries = entries.remove(&profile.id).unwrap_or_default();
+            profile.mods = mod_entries.iter().map(|m| m.mod_id.clone()).collect();
+            ProfileDetails { profile, mod_entries }
+        })
+        .collect())
+}
+
+fn load_template(conn: &Connection, template_id: &str) -> Result<ProfileTemplate> {
+    let mut template = conn
+        .query_row(
+            "SELECT id, name, description, icon, color, created FROM profile_templates WHERE id = ?1",
+            [template_id],
+            |row| {
+                Ok(ProfileTemplate {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                    icon: row.get(3)?,
+                    color: row.get(4)?,
+                    created: row.get(5)?,
+                    mods: vec![],
+                })
+            },
+        )
+        .optional()?
+        .ok_or_else(|| AppError::Custom(format!("Template not found: {}", template_id)))?;
+
+    let mut stmt = conn.prepare("SELECT mod_id FROM profile_template_mods WHERE template_id = ?1 ORDER BY mod_id")?;
+    template.mods = stmt.query_map([template_id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+    Ok(template)
+}
+
+/// Inserts an inactive profile with no mods and returns its id.
+pub fn insert_profile(conn: &Connection, name: &str, description: &str, icon: &str, color: &str) -> Result<String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO profiles (id, name, description, icon, color, active, created, last_used, play_time)
+         VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?6, 0)",
+        (&id, name, description, icon, color, &now),
+    )?;
+    Ok(id)
+}
+
+/// Trims a profile name and checks it is usable and not taken by another profile
+/// (case-insensitively). `exclude_id` is the profile being renamed, if any.
+pub fn validate_name(conn: &Connection, name: &str, exclude_id: Option<&str>) -> Result<String> {
+    let name = validation::normalize_profile_name(name).ok_or_else(|| {
+        AppError::Validation(format!(
+            "Profile name must be between 1 and {} characters",
+            validation::MAX_PROFILE_NAME_LEN
+        ))
+    })?;
+
+    let taken: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM profiles WHERE name = ?1 COLLATE NOCASE AND id != ?2)",
+        (&name, exclude_id.unwrap_or_default()),
+        |row| row.get(0),
+    )?;
+    if taken {
+        return Err(AppError::Validation(format!("A profile named '{}' already exists", name)));
+    }
+    Ok(name)
+}
+
+fn validate_template_name(conn: &Connection, name: &str) -> Result<String> {
+    let name = validation::normalize_profile_name(name).ok_or_else(|| {
+        AppError::Validation(format!(
+            "Template name must be between 1 and {} characters",
+            validation::MAX_PROFILE_NAME_LEN
+        ))
+    })?;
+
+    let taken: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM profile_templates WHERE name = ?1 COLLATE NOCASE)",
+        [&name],
+        |row| row.get(0),
+    )?;
+    if taken {
+        return Err(AppError::Validation(format!("A template named '{}' already exists", name)));
+    }
+    Ok(name)
+}
+
+/// BepInEx root of a profile when profiles are isolated: `<app data>/profiles/<id>/BepInEx`.
+pub fn profile_bepinex_dir(app_data_dir: &Path, profile_id: &str) -> PathBuf {
+    app_data_dir.join("profiles").join(profile_id).join("BepInEx")
+}
+
+/// Folder holding a template's saved configs: `<app data>/templates/<id>`.
+pub fn template_dir(app_data_dir: &Path, template_id: &str) -> PathBuf {
+    app_data_dir.join("templates").join(template_id)
+}
+
+/// Copies the `config` folder found under `from` (a BepInEx root or template folder) to `to`,
+/// replacing what is there. Does nothing when `from` has no configs.
+pub fn copy_config(from: &Path, to: &Path) -> Result<()> {
+    let source = from.join("config");
+    if !source.is_dir() {
+        return Ok(());
+    }
+    let target = to.join("config");
+    if target.exists() {
+        fs::remove_dir_all(&target)?;
+    }
+    file_ops::copy_dir_all(&source, &target)?;
+    Ok(())
+}
+
+/// Creates a profile's own BepInEx root. BepInEx resolves config, plugins, patchers and
+/// its log relative to the folder its preloader is loaded from, so the game's `core`
+/// folder is copied in. On first creation the game's configs are copied as a starting point.
+pub fn ensure_bepinex_root(app_data_dir: &Path, profile_id: &str, valheim_path: &Path) -> Result<PathBuf> {
+    let root = profile_bepinex_dir(app_data_dir, profile_id);
+    let game_bepinex = valheim_path.join("BepInEx");
+    let first_run = !root.exists();
+
+    let core = root.join("core");
+    if !core.exists() {
+        let game_core = game_bepinex.join("core");
+        if !game_core.exists() {
+            return Err(AppError::BepInExNotInstalled);
+        }
+        file_ops::copy_dir_all(&game_core, &core)?;
+    }
+
+    if first_run && game_bepinex.join("config").exists() {
+        file_ops::copy_dir_all(&game_bepinex.join("config"), &root.join("config"))?;
+    }
+
+    for dir in BEPINEX_SUBDIRS {
+        fs::create_dir_all(root.join(dir))?;
+    }
+    Ok(root)
+}
+
+/// Plugins folder entries created by Deftheim: links into the repository and, on Windows,
+/// hardlinked copies named after a repository package. Anything else is left untouched.
+/// Dangling links are not reported, so they get unlinked and relinked.
+fn managed_entries(repository_path: &Path, plugins_dir: &Path) -> Result<BTreeSet<String>> {
+    let mut entries = BTreeSet::new();
+    let mut dangling = Vec::new();
+
+    for entry in fs::read_dir(plugins_dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let managed = file_ops::is_link_into(&path, repository_path)
+            || (cfg!(target_os = "windows") && path.is_dir() && repository_path.join(&name).is_dir());
+        if !managed {
+            continue;
+        }
+
+        if path.exists() {
+            entries.insert(name);
+        } else {
+            dangling.push(path);
+        }
+    }
+
+    for path in dangling {
+        file_ops::unlink_dir(&path)?;
+    }
+    Ok(entries)
+}
+
+fn apply_ops(
+    repository_path: &Path,
+    plugins_dir: &Path,
+    to_unlink: &[String],
+    to_link: &[String],
+    applied: &mut Vec<LinkOp>,
+) -> Result<()> {
+    for mod_id in to_unlink {
+        file_ops::unlink_dir(&plugins_dir.join(mod_id))?;
+        applied.push(LinkOp::Unlinked(mod_id.clone()));
+    }
+    for mod_id in to_link {
+        file_ops::link_dir(&repository_path.join(mod_id), &plugins_dir.join(mod_id))?;
+        applied.push(LinkOp::Linked(mod_id.clone()));
+    }
+    Ok(())
+}
+
+fn undo_ops(repository_path: &Path, plugins_dir: &Path, applied: Vec<LinkOp>) {
+    for op in applied.into_iter().rev() {
+        let undone = match &op {
+            LinkOp::Linked(mod_id) => file_ops::unlink_dir(&plugins_dir.join(mod_id)),
+            LinkOp::Unlinked(mod_id) => file_ops::link_dir(&repository_path.join(mod_id), &plugins_dir.join(mod_id)),
+        };
+        if let Err(e) = undone {
+            let (LinkOp::Linked(mod_id) | LinkOp::Unlinked(mod_id)) = &op;
+            tracing::error!("Could not restore plugin link for {}: {}", mod_id, e);
+        }
+    }
+}
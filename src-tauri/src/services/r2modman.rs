@@ -0,0 +1,245 @@
+use crate::error::{AppError, Result};
+use base64::{engine::general_purpose, Engine as _};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use crate::utils::file_ops;
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// Name of the profile description inside an `.r2z` archive.
+const EXPORT_FILE: &str = "export.r2x";
+/// Header r2modman puts in front of the base64 archive when sharing a profile code.
+const CODE_HEADER: &str = "#r2modman";
+/// Archive folder holding BepInEx configs, relative to the r2modman profile root.
+const CONFIG_PREFIX: &str = "BepInEx/config/";
+
+/// `export.r2x`, the YAML profile description r2modman writes into `.r2z` archives.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct R2Export {
+    pub profile_name: String,
+    #[serde(default)]
+    pub mods: Vec<R2Mod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct R2Mod {
+    /// Package name without version ("Team-Name").
+    pub name: String,
+    pub version: R2Version,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct R2Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl R2Mod {
+    /// Versioned id as Deftheim and Thunderstore use it ("Team-Name-1.2.3").
+    pub fn mod_id(&self) -> String {
+        format!("{}-{}.{}.{}", self.name, self.version.major, self.version.minor, self.version.patch)
+    }
+}
+
+impl R2Version {
+    pub fn parse(version: &str) -> Option<Self> {
+        let parsed = crate::utils::version::parse(version)?;
+        Some(Self { major: parsed.major, minor: parsed.minor, patch: parsed.patch })
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Contents of an `.r2z` archive: the profile description and its config files, keyed
+/// by their path below `BepInEx/config`.
+pub struct R2Archive {
+    pub export: R2Export,
+    pub configs: Vec<(PathBuf, Vec<u8>)>,
+}
+
+pub fn read_r2z(data: &[u8]) -> Result<R2Archive> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(zip_error)?;
+    let mut export = None;
+    let mut configs = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(zip_error)?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().replace('\\', "/");
+
+        if name == EXPORT_FILE {
+            let mut yaml = String::new();
+            file.read_to_string(&mut yaml)?;
+            let parsed: R2Export = serde_yaml::from_str(&yaml)
+                .map_err(|e| AppError::Custom(format!("Invalid {}: {}", EXPORT_FILE, e)))?;
+            export = Some(parsed);
+        } else if let Some(relative) = name.strip_prefix(CONFIG_PREFIX).and_then(file_ops::safe_relative_path) {
+            let mut content = Vec::new();
+            file.read_to_end(&mut content)?;
+            configs.push((relative, content));
+        }
+    }
+
+    let export = export.ok_or_else(|| AppError::Custom(format!("Archive has no {}", EXPORT_FILE)))?;
+    Ok(R2Archive { export, configs })
+}
+
+/// Builds an `.r2z` archive, bundling the files below `config_dir` when it exists.
+pub fn write_r2z(export: &R2Export, config_dir: Option<&Path>) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+
+    let yaml = serde_yaml::to_string(export).map_err(|e| AppError::Custom(e.to_string()))?;
+    zip.start_file(EXPORT_FILE, options).map_err(zip_error)?;
+    zip.write_all(yaml.as_bytes())?;
+
+    if let Some(dir) = config_dir.filter(|dir| dir.is_dir()) {
+        for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(dir).map_err(|e| AppError::Custom(e.to_string()))?;
+            let name = relative.components().fold(CONFIG_PREFIX.trim_end_matches('/').to_string(), |name, part| {
+                format!("{}/{}", name, part.as_os_str().to_string_lossy())
+            });
+            zip.start_file(name, options).map_err(zip_error)?;
+            zip.write_all(&fs::read(entry.path())?)?;
+        }
+    }
+
+    Ok(zip.finish().map_err(zip_error)?.into_inner())
+}
+
+/// Downloads the archive behind an r2modman profile code from the legacy profile API.
+pub async fn fetch_code(endpoint: &str, code: &str) -> Result<Vec<u8>> {
+    let url = format!("{}/get/{}/", endpoint.trim_end_matches('/'), code.trim());
+    let response = client()?.get(&url).send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(AppError::Validation(format!("Profile code {} does not exist or has expired", code.trim())));
+    }
+    let body = response.error_for_status()?.text().await?;
+
+    let encoded = body
+        .trim()
+        .strip_prefix(CODE_HEADER)
+        .ok_or_else(|| AppError::Custom("Profile code does not point to an r2modman profile".to_string()))?;
+    general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| AppError::Custom(format!("Base64 decode failed: {}", e)))
+}
+
+/// Uploads an `.r2z` archive to the legacy profile API and returns the profile code.
+pub async fn upload_code(endpoint: &str, archive: &[u8]) -> Result<String> {
+    #[derive(Deserialize)]
+    struct CreateResponse {
+        key: String,
+    }
+
+    let url = format!("{}/create/", endpoint.trim_end_matches('/'));
+    let body = format!("{}\n{}", CODE_HEADER, general_purpose::STANDARD.encode(archive));
+    let response: CreateResponse = client()?
+        .post(&url)
+        .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(response.key)
+}
+
+/// An entry of a profile's `mods.yml`, the list r2modman keeps for every profile.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManagedMod {
+    pub name: String,
+    pub version_number: R2Version,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl ManagedMod {
+    pub fn mod_id(&self) -> String {
+        let v = self.version_number;
+        format!("{}-{}.{}.{}", self.name, v.major, v.minor, v.patch)
+    }
+}
+
+/// A profile found in an r2modman data folder.
+pub struct InstalledProfile {
+    pub name: String,
+    pub dir: PathBuf,
+    pub mods: Vec<ManagedMod>,
+}
+
+/// Valheim data folders of r2modman and Thunderstore Mod Manager that exist on this machine.
+pub fn data_dirs() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Some(config) = dirs::config_dir() {
+        candidates.push(config.join("r2modmanPlus-local").join("Valheim"));
+        candidates.push(config.join("Thunderstore Mod Manager").join("DataFolder").join("Valheim"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        // Flatpak build of r2modman
+        candidates.push(
+            home.join(".var/app/com.github.ebkr.r2modman/config/r2modmanPlus-local").join("Valheim"),
+        );
+    }
+    candidates.into_iter().filter(|dir| dir.join("profiles").is_dir()).collect()
+}
+
+/// Reads every profile below `<data dir>/profiles` that has a `mods.yml`.
+pub fn read_profiles(data_dir: &Path) -> Result<Vec<InstalledProfile>> {
+    let mut profiles = Vec::new();
+    for entry in fs::read_dir(data_dir.join("profiles"))? {
+        let dir = entry?.path();
+        let mods_file = dir.join("mods.yml");
+        if !mods_file.is_file() {
+            continue;
+        }
+
+        let content = fs::read_to_string(&mods_file)?;
+        let mods: Vec<ManagedMod> = if content.trim().is_empty() {
+            vec![]
+        } else {
+            serde_yaml::from_str(&content)
+                .map_err(|e| AppError::Custom(format!("Invalid {}: {}", mods_file.display(), e)))?
+        };
+        let name = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+        profiles.push(InstalledProfile { name, dir, mods });
+    }
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(profiles)
+}
+
+/// r2modman keeps every downloaded package extracted in `<data dir>/cache/<Team-Name>/<version>`.
+pub fn cached_package(data_dir: &Path, managed: &ManagedMod) -> Option<PathBuf> {
+    let v = managed.version_number;
+    let dir = data_dir
+        .join("cache")
+        .join(&managed.name)
+        .join(format!("{}.{}.{}", v.major, v.minor, v.patch));
+    dir.is_dir().then_some(dir)
+}
+
+fn client() -> Result<Client> {
+    Ok(Client::builder().timeout(Duration::from_secs(60)).build()?)
+}
+
+fn zip_error(e: zip::result::ZipError) -> AppError {
+    AppError::Custom(format!("Archive error: {}", e))
+}
@@ -0,0 +1,106 @@
+use crate::commands::mod_operations;
+use crate::error::Result;
+use crate::models::UnresolvedMod;
+use crate::services::profile_import::ImportEntry;
+use crate::utils::version;
+use rusqlite::Connection;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Thunderstore categories telling which side a package runs on.
+const SERVER_SIDE: &str = "Server-side";
+const CLIENT_SIDE: &str = "Client-side";
+
+/// Plugins found in a server install, identified as catalog packages where possible.
+pub struct ServerScan {
+    pub plugins_dir: PathBuf,
+    /// Versioned package ids ("Team-Name-1.2.3").
+    pub packages: Vec<String>,
+    /// Plugin folders and files that could not be matched to a package.
+    pub unmatched: Vec<UnresolvedMod>,
+}
+
+/// The plugins folder below `path`, which may be the server folder, its `BepInEx` folder,
+/// the plugins folder itself or a repository-style folder of package folders.
+pub fn plugins_dir(path: &Path) -> PathBuf {
+    [path.join("BepInEx").join("plugins"), path.join("plugins")]
+        .into_iter()
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Identifies each entry of the server's plugins folder by its `manifest.json`, the same
+/// way the repository is scanned.
+pub fn scan(conn: &Connection, path: &Path) -> Result<ServerScan> {
+    let plugins_dir = plugins_dir(path);
+    let mut packages = Vec::new();
+    let mut unmatched = Vec::new();
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(&plugins_dir)?.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    entries.sort();
+
+    for entry in entries {
+        let name = entry.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if !entry.is_dir() {
+            if name.to_lowercase().ends_with(".dll") {
+                unmatched.push(UnresolvedMod { name, reason: "Loose plugin file without a manifest".to_string() });
+            }
+            continue;
+        }
+        let Some(manifest) = mod_operations::read_manifest(&entry) else {
+            unmatched.push(UnresolvedMod { name, reason: "No manifest.json to identify the package".to_string() });
+            continue;
+        };
+        match identify(conn, &name, &manifest.name, &manifest.version_number)? {
+            Ok(mod_id) => packages.push(mod_id),
+            Err(reason) => unmatched.push(UnresolvedMod { name, reason }),
+        }
+    }
+
+    Ok(ServerScan { plugins_dir, packages, unmatched })
+}
+
+/// Entries for the client profile; server-only packages are listed but disabled.
+pub fn import_entries(conn: &Connection, packages: &[String]) -> Result<(Vec<ImportEntry>, Vec<String>)> {
+    let mut entries = Vec::new();
+    let mut server_only = Vec::new();
+    for mod_id in packages {
+        let package = version::split_package_id(mod_id).map_or(mod_id.as_str(), |(p, _)| p);
+        let is_server_only = is_server_only(conn, package)?;
+        if is_server_only {
+            server_only.push(mod_id.clone());
+        }
+        entries.push(ImportEntry { name: mod_id.clone(), enabled: !is_server_only });
+    }
+    Ok((entries, server_only))
+}
+
+/// Tags a package as "Server-side" without "Client-side" in the catalog.
+fn is_server_only(conn: &Connection, package: &str) -> Result<bool> {
+    let mut stmt = conn.prepare("SELECT category FROM mod_categories WHERE mod_id = ?1")?;
+    let categories: Vec<String> = stmt.query_map([package], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+    Ok(categories.iter().any(|c| c == SERVER_SIDE) && !categories.iter().any(|c| c == CLIENT_SIDE))
+}
+
+/// Works out the versioned package id of a plugin folder. Mod managers name the folder
+/// "Team-Name" and repositories "Team-Name-1.2.3"; for any other folder name the
+/// manifest's package name must be unique in the catalog. The inner `Err` is a
+/// user-facing reason.
+fn identify(conn: &Connection, folder: &str, name: &str, ver: &str) -> Result<std::result::Result<String, String>> {
+    if let Some((package, _)) = version::split_package_id(folder) {
+        if package.ends_with(&format!("-{}", name)) {
+            return Ok(Ok(folder.to_string()));
+        }
+    }
+    if folder.ends_with(&format!("-{}", name)) && folder.len() > name.len() + 1 {
+        return Ok(Ok(format!("{}-{}", folder, ver)));
+    }
+
+    let mut stmt = conn.prepare("SELECT full_name FROM mod_versions WHERE name = ?1 AND version_number = ?2")?;
+    let matches: Vec<String> = stmt.query_map((name, ver), |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+    Ok(match matches.as_slice() {
+        [mod_id] => Ok(mod_id.clone()),
+        [] => Err(format!("{} {} is not in the catalog", name, ver)),
+        _ => Err(format!("{} {} matches several packages: {}", name, ver, matches.join(", "))),
+    })
+}
@@ -0,0 +1,221 @@
+use crate::error::{AppError, Result};
+use crate::services::backup_scheduler::{self, Trigger};
+use crate::state::AppState;
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::process::Child;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessesToUpdate, System};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the game process is checked.
+const POLL: Duration = Duration::from_secs(5);
+/// How often a running session records that the game is still alive, which bounds the
+/// play time lost if Deftheim is closed and the game exits before it is started again.
+const HEARTBEAT: Duration = Duration::from_secs(60);
+/// Valheim started outside Steam restarts itself through Steam and exits at once; the
+/// real game process is looked for this long after such a quick exit.
+const HANDOVER_WINDOW: Duration = Duration::from_secs(30);
+
+enum Watched {
+    Child(Child),
+    Pid(u32),
+    /// The launched process exited early; waiting for Steam to start the game.
+    Handover { exited_at: DateTime<Utc>, exit_code: Option<i32>, deadline: Instant },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionEvent {
+    session_id: i64,
+    profile_id: Option<String>,
+}
+
+/// Records a new session for a launched game and follows the process in the background.
+pub fn track(app: AppHandle, profile_id: Option<String>, child: Child) -> Result<i64> {
+    let started_at = Utc::now();
+    let session_id = {
+        let state = app.state::<AppState>();
+        let conn = state.db.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        conn.execute(
+            "INSERT INTO play_sessions (profile_id, pid, started_at, last_seen_at) VALUES (?1, ?2, ?3, ?3)",
+            (&profile_id, child.id(), started_at.to_rfc3339()),
+        )?;
+        if let Some(id) = &profile_id {
+            conn.execute("UPDATE profiles SET last_used = ?2 WHERE id = ?1", (id, started_at.to_rfc3339()))?;
+        }
+        conn.last_insert_rowid()
+    };
+
+    let _ = app.emit("game-started", SessionEvent { session_id, profile_id: profile_id.clone() });
+    tauri::async_runtime::spawn(watch(app, session_id, profile_id, started_at, Watched::Child(child)));
+    Ok(session_id)
+}
+
+/// Picks up sessions left open by a previous run: games still running are followed again,
+/// the others are closed at their last heartbeat.
+pub fn resume(app: AppHandle) {
+    let state = app.state::<AppState>();
+    let open: Vec<(i64, Option<String>, u32, String, String)> = {
+        let Ok(conn) = state.db.lock() else {
+            return;
+        };
+        let rows = conn
+            .prepare("SELECT id, profile_id, pid, started_at, last_seen_at FROM play_sessions WHERE ended_at IS NULL")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            });
+        match rows {
+            Ok(rows) => rows,
+            Err(e) => {
+                tracing::warn!("Could not load open play sessions: {}", e);
+                return;
+            }
+        }
+    };
+
+    for (session_id, profile_id, pid, started_at, last_seen_at) in open {
+        let started_at = parse_time(&started_at);
+        if is_game_process(pid) {
+            tracing::info!("Game from session {} is still running, resuming tracking", session_id);
+            tauri::async_runtime::spawn(watch(app.clone(), session_id, profile_id, started_at, Watched::Pid(pid)));
+        } else {
+            let result = state
+                .db
+                .lock()
+                .map_err(|_| AppError::Custom("DB lock poisoned".to_string()))
+                .and_then(|conn| finish(&conn, session_id, started_at, parse_time(&last_seen_at), None));
+            if let Err(e) = result {
+                tracing::warn!("Could not close play session {}: {}", session_id, e);
+            }
+        }
+    }
+}
+
+async fn watch(app: AppHandle, session_id: i64, profile_id: Option<String>, started_at: DateTime<Utc>, mut watched: Watched) {
+    let mut last_beat = Instant::now();
+
+    loop {
+        tokio::time::sleep(POLL).await;
+
+        let ended = match &mut watched {
+            Watched::Child(child) => match child.try_wait() {
+                Ok(None) => None,
+                Ok(Some(status)) => Some(status.code()),
+                Err(e) => {
+                    tracing::warn!("Lost track of the game process: {}", e);
+                    Some(None)
+                }
+            },
+            Watched::Pid(pid) => (!is_game_process(*pid)).then_some(None),
+            Watched::Handover { exited_at, exit_code, deadline } => match find_game_process(started_at) {
+                Some(pid) => {
+                    tracing::info!("Game restarted through Steam as process {}", pid);
+                    set_pid(&app, session_id, pid);
+                    watched = Watched::Pid(pid);
+                    continue;
+                }
+                None if Instant::now() >= *deadline => {
+                    close(&app, session_id, &profile_id, started_at, *exited_at, *exit_code);
+                    return;
+                }
+                None => continue,
+            },
+        };
+
+        if let Some(exit_code) = ended {
+            let now = Utc::now();
+            let quick_exit = (now - started_at).to_std().is_ok_and(|elapsed| elapsed < HANDOVER_WINDOW);
+            if quick_exit && matches!(watched, Watched::Child(_)) {
+                watched = Watched::Handover { exited_at: now, exit_code, deadline: Instant::now() + HANDOVER_WINDOW };
+                continue;
+            }
+            close(&app, session_id, &profile_id, started_at, now, exit_code);
+            return;
+        }
+
+        if last_beat.elapsed() >= HEARTBEAT {
+            last_beat = Instant::now();
+            let state = app.state::<AppState>();
+            if let Ok(conn) = state.db.lock() {
+                let _ = conn.execute(
+                    "UPDATE play_sessions SET last_seen_at = ?2 WHERE id = ?1",
+                    (session_id, Utc::now().to_rfc3339()),
+                );
+            }
+        }
+    }
+}
+
+fn close(app: &AppHandle, session_id: i64, profile_id: &Option<String>, started_at: DateTime<Utc>, ended_at: DateTime<Utc>, exit_code: Option<i32>) {
+    let state = app.state::<AppState>();
+    let result = state
+        .db
+        .lock()
+        .map_err(|_| AppError::Custom("DB lock poisoned".to_string()))
+        .and_then(|conn| finish(&conn, session_id, started_at, ended_at, exit_code));
+    match result {
+        Ok(()) => tracing::info!("Play session {} ended with exit code {:?}", session_id, exit_code),
+        Err(e) => tracing::warn!("Could not close play session {}: {}", session_id, e),
+    }
+    let _ = app.emit("game-exited", SessionEvent { session_id, profile_id: profile_id.clone() });
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { backup_scheduler::run(&app, Trigger::Exit).await });
+}
+
+/// Closes a session and adds its duration to the profile's play time.
+fn finish(conn: &Connection, session_id: i64, started_at: DateTime<Utc>, ended_at: DateTime<Utc>, exit_code: Option<i32>) -> Result<()> {
+    let duration = (ended_at - started_at).num_seconds().max(0);
+    let ended = ended_at.to_rfc3339();
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE play_sessions SET ended_at = ?2, last_seen_at = ?2, exit_code = ?3, duration_seconds = ?4 WHERE id = ?1",
+        (session_id, &ended, exit_code, duration),
+    )?;
+    tx.execute(
+        "UPDATE profiles SET play_time = play_time + ?2, last_used = ?3
+         WHERE id = (SELECT profile_id FROM play_sessions WHERE id = ?1)",
+        (session_id, duration, &ended),
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn set_pid(app: &AppHandle, session_id: i64, pid: u32) {
+    let state = app.state::<AppState>();
+    if let Ok(conn) = state.db.lock() {
+        let _ = conn.execute("UPDATE play_sessions SET pid = ?2 WHERE id = ?1", (session_id, pid));
+    }
+}
+
+fn is_game_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.starts_with("valheim") && !name.contains("server")
+}
+
+/// True if `pid` is a running Valheim client; checks the name since PIDs get reused.
+fn is_game_process(pid: u32) -> bool {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    system.process(pid).is_some_and(|p| is_game_name(&p.name().to_string_lossy()))
+}
+
+/// A Valheim client started after `since`.
+fn find_game_process(since: DateTime<Utc>) -> Option<u32> {
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    let since = u64::try_from(since.timestamp()).unwrap_or_default();
+    system
+        .processes()
+        .values()
+        .find(|p| p.start_time() >= since && is_game_name(&p.name().to_string_lossy()))
+        .map(|p| p.pid().as_u32())
+}
+
+fn parse_time(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value).map(|t| t.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now())
+}
@@ -4,6 +4,7 @@ use reqwest::Client;
 use std::time::Duration;
 
 const API_BASE_URL: &str = "https://thunderstore.io/c/valheim/api/v1";
+const EXPERIMENTAL_API_URL: &str = "https://thunderstore.io/api/experimental";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackageVersion {
@@ -22,6 +23,11 @@ pub struct PackageVersion {
     pub file_size: u64,
 }
 
+#[derive(Debug, Deserialize)]
+struct MarkdownResponse {
+    markdown: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackageListing {
     pub name: String,
@@ -53,3 +59,21 @@ pub async fn fetch_packages() -> Result<Vec<PackageListing>> {
     tracing::info!("Fetched {} packages", packages.len());
     Ok(packages)
 }
+
+/// Fetches the CHANGELOG.md rendered by Thunderstore for a single package version.
+/// Returns `None` when the package was uploaded without a changelog.
+pub async fn fetch_changelog(owner: &str, name: &str, version: &str) -> Result<Option<String>> {
+    tracing::info!("Fetching changelog for {}-{}-{}", owner, name, version);
+    let client = Client::builder()
+        .timeout(Duration::from_secs(15))
+        .build()?;
+
+    let url = format!("{}/package/{}/{}/{}/changelog/", EXPERIMENTAL_API_URL, owner, name, version);
+    let response = client.get(&url).send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let body: MarkdownResponse = response.error_for_status()?.json().await?;
+    Ok(body.markdown)
+}
@@ -87,6 +87,14 @@ impl ThunderstoreService {
                 ),
             )?;
 
+            conn.execute("DELETE FROM mod_categories WHERE mod_id = ?1", [&pkg.full_name])?;
+            for category in &pkg.categories {
+                conn.execute(
+                    "INSERT OR IGNORE INTO mod_categories (mod_id, category) VALUES (?1, ?2)",
+                    (&pkg.full_name, category),
+                )?;
+            }
+
             for ver in &pkg.versions {
                 conn.execute(
                     "INSERT OR REPLACE INTO mod_versions (full_name, mod_id, name, description, icon, version_number, download_url, downloads, date_created, website_url, is_active, uuid4, file_size)
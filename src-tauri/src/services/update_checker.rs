@@ -0,0 +1,246 @@
+use crate::error::{AppError, Result};
+use crate::models::UpdatePolicy;
+use crate::services::thunderstore;
+use crate::utils::version;
+use regex::Regex;
+use rusqlite::{Connection, OptionalExtension};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const CHANGELOG_FILES: &[&str] = &["CHANGELOG.md", "changelog.md", "Changelog.md"];
+
+pub struct UpdateChecker {
+    db_conn: Arc<Mutex<Connection>>,
+}
+
+impl UpdateChecker {
+    pub fn new(db_conn: Arc<Mutex<Connection>>) -> Self {
+        Self { db_conn }
+    }
+
+    /// Returns the changelog entries published after `installed` up to and including `latest`.
+    /// The changelog of the latest version is read from the cache, or fetched from
+    /// Thunderstore and cached on first use.
+    pub async fn changelog_between(&self, package_name: &str, installed: &str, latest: &str) -> Result<String> {
+        let version_full_name = format!("{}-{}", package_name, latest);
+
+        let markdown = match self.cached_changelog(&version_full_name)? {
+            Some(markdown) => markdown,
+            None => {
+                let (owner, name) = package_name
+                    .split_once('-')
+                    .ok_or_else(|| AppError::Custom(format!("Invalid package name: {}", package_name)))?;
+                let markdown = thunderstore::fetch_changelog(owner, name, latest).await?.unwrap_or_default();
+                self.cache_changelog(&version_full_name, &markdown)?;
+                markdown
+            }
+        };
+
+        Ok(extract_entries(&markdown, installed, latest))
+    }
+
+    /// Caches the CHANGELOG.md shipped inside an extracted package archive, if any.
+    pub fn cache_changelog_from_dir(&self, version_full_name: &str, package_dir: &Path) -> Result<()> {
+        for file in CHANGELOG_FILES {
+            let path = package_dir.join(file);
+            if path.is_file() {
+                let markdown = fs::read_to_string(path)?;
+                return self.cache_changelog(version_full_name, &markdown);
+            }
+        }
+        Ok(())
+    }
+
+    fn cached_changelog(&self, version_full_name: &str) -> Result<Option<String>> {
+        let conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        let markdown = conn
+            .query_row(
+                "SELECT markdown FROM mod_changelogs WHERE version_full_name = ?1",
+                [version_full_name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(markdown)
+    }
+
+    fn cache_changelog(&self, version_full_name: &str, markdown: &str) -> Result<()> {
+        let conn = self.db_conn.lock().map_err(|_| AppError::Custom("DB lock poisoned".to_string()))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO mod_changelogs (version_full_name, markdown, fetched_at) VALUES (?1, ?2, ?3)",
+            (version_full_name, markdown, chrono::Utc::now().to_rfc3339()),
+        )?;
+        Ok(())
+    }
+}
+
+/// Splits a changelog into per-version sections and keeps those in `(installed, latest]`.
+/// Changelogs without recognisable version headings are returned whole.
+pub fn extract_entries(markdown: &str, installed: &str, latest: &str) -> String {
+    let heading = Regex::new(r"(?i)^\s*(?:#{1,6}\s*|\*\*\s*|-\s*)?\[?(?:version\s*|v)?(\d+\.\d+\.\d+)\b")
+        .expect("valid changelog heading regex");
+
+    let mut sections: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in markdown.lines() {
+        match heading.captures(line) {
+            Some(cap) => sections.push((cap[1].to_string(), vec![line])),
+            None => {
+                if let Some((_, lines)) = sections.last_mut() {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+
+    if sections.is_empty() {
+        return markdown.trim().to_string();
+    }
+
+    let (installed, latest) = match (version::parse(installed), version::parse(latest)) {
+        (Some(i), Some(l)) => (i, l),
+        _ => return markdown.trim().to_string(),
+    };
+
+    sections
+        .into_iter()
+        .filter(|(ver, _)| version::parse(ver).is_some_and(|v| v > installed && v <= latest))
+        .map(|(_, lines)| lines.join("\n").trim().to_string())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Resolves the update policy for a package. Every matching rule is considered —
+/// the package-specific and wildcard rules, globally and for each profile that uses
+/// the package (or only `profile_id`, when given) — and the most restrictive wins.
+/// Packages without any matching rule may update freely.
+pub fn effective_policy(conn: &Connection, package_name: &str, profile_id: Option<&str>) -> Result<UpdatePolicy> {
+    let policies: Vec<String> = match profile_id {
+        Some(profile_id) => {
+            let mut stmt = conn.prepare(
+                "SELECT policy FROM update_policies
+                 WHERE package_name IN ('', ?1) AND profile_id IN ('', ?2)"
+            )?;
+            stmt.query_map((package_name, profile_id), |row| row.get(0))?.collect::<rusqlite::Result<_>>()?
+        }
+        None => {
+            // The prefix also matches longer package names ("Team-Name-Extra-1.0.0" for
+            // "Team-Name"), so filter exactly
+            let mut stmt = conn.prepare(
+                "SELECT DISTINCT profile_id, mod_id FROM profile_mods
+                 WHERE mod_id = ?1 OR substr(mod_id, 1, length(?1) + 1) = ?1 || '-'"
+            )?;
+            let profile_ids: Vec<String> = stmt
+                .query_map([package_name], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+                .into_iter()
+                .filter(|(_, mod_id)| {
+                    mod_id == package_name || version::split_package_id(mod_id).is_some_and(|(p, _)| p == package_name)
+                })
+                .map(|(profile_id, _)| profile_id)
+                .collect();
+
+            let mut stmt = conn.prepare("SELECT profile_id, policy FROM update_policies WHERE package_name IN ('', ?1)")?;
+            stmt.query_map([package_name], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+                .into_iter()
+                .filter(|(profile_id, _)| profile_id.is_empty() || profile_ids.contains(profile_id))
+                .map(|(_, policy)| policy)
+                .collect()
+        }
+    };
+
+    Ok(policies
+        .iter()
+        .filter_map(|p| UpdatePolicy::parse(p))
+        .min()
+        .unwrap_or(UpdatePolicy::Any))
+}
+
+/// Returns true if `policy` permits moving from `installed` to `candidate`.
+pub fn policy_allows(policy: UpdatePolicy, installed: &str, candidate: &str) -> bool {
+    let (Some(installed), Some(candidate)) = (version::parse(installed), version::parse(candidate)) else {
+        return policy == UpdatePolicy::Any;
+    };
+
+    match policy {
+        UpdatePolicy::Hold => false,
+        UpdatePolicy::PatchOnly => candidate.major == installed.major && candidate.minor == installed.minor,
+        UpdatePolicy::Minor => candidate.major == installed.major,
+        UpdatePolicy::Any => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_entries_keeps_versions_after_installed_up_to_latest() {
+        let markdown = "# Changelog\n\n## 1.3.0\n- New\n\n## 1.2.3\n- Fix\n\n## 1.2.2\n- Old\n";
+        assert_eq!(extract_entries(markdown, "1.2.2", "1.2.3"), "## 1.2.3\n- Fix");
+        assert_eq!(extract_entries(markdown, "1.2.2", "1.3.0"), "## 1.3.0\n- New\n\n## 1.2.3\n- Fix");
+    }
+
+    #[test]
+    fn extract_entries_recognises_bold_and_list_headings() {
+        let bold = "**v1.2.3**\nFix\n**v1.2.2**\nOld\n";
+        assert_eq!(extract_entries(bold, "1.2.2", "1.2.3"), "**v1.2.3**\nFix");
+
+        let list = "- 1.2.3\n  - Fix\n- 1.2.2\n  - Old\n";
+        assert_eq!(extract_entries(list, "1.2.2", "1.2.3"), "- 1.2.3\n  - Fix");
+    }
+
+    #[test]
+    fn extract_entries_without_version_headings_returns_everything() {
+        let markdown = "\nJust some release notes.\n\n- Fixed things\n";
+        assert_eq!(extract_entries(markdown, "1.0.0", "1.1.0"), markdown.trim());
+    }
+
+    #[test]
+    fn extract_entries_with_unparseable_installed_version_returns_everything() {
+        let markdown = "## 1.2.3\n- Fix\n\n## 1.2.2\n- Old\n";
+        assert_eq!(extract_entries(markdown, "unknown", "1.2.3"), markdown.trim());
+    }
+
+    #[test]
+    fn policy_allows_respects_each_level() {
+        assert!(!policy_allows(UpdatePolicy::Hold, "1.2.3", "1.2.4"));
+
+        assert!(policy_allows(UpdatePolicy::PatchOnly, "1.2.3", "1.2.4"));
+        assert!(!policy_allows(UpdatePolicy::PatchOnly, "1.2.3", "1.3.0"));
+
+        assert!(policy_allows(UpdatePolicy::Minor, "1.2.3", "1.3.0"));
+        assert!(!policy_allows(UpdatePolicy::Minor, "1.2.3", "2.0.0"));
+
+        assert!(policy_allows(UpdatePolicy::Any, "1.2.3", "2.0.0"));
+    }
+
+    #[test]
+    fn policy_allows_only_any_for_unparseable_versions() {
+        assert!(!policy_allows(UpdatePolicy::PatchOnly, "latest", "1.2.4"));
+        assert!(!policy_allows(UpdatePolicy::Minor, "1.2.3", "next"));
+        assert!(policy_allows(UpdatePolicy::Any, "latest", "1.2.4"));
+    }
+
+    #[test]
+    fn effective_policy_ignores_profiles_using_a_longer_package_name() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::schema::create_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO profiles (id, name, description, icon, color, created, last_used) VALUES
+                 ('a', 'A', '', '', '', '', ''),
+                 ('b', 'B', '', '', '', '', '');
+             INSERT INTO profile_mods (profile_id, mod_id, version) VALUES
+                 ('a', 'Team-Name-1.0.0', '1.0.0'),
+                 ('b', 'Team-Name-Extra-1.0.0', '1.0.0');
+             INSERT INTO update_policies (profile_id, package_name, policy) VALUES
+                 ('a', '', 'minor'),
+                 ('b', '', 'hold');",
+        )
+        .unwrap();
+
+        assert_eq!(effective_policy(&conn, "Team-Name", None).unwrap(), UpdatePolicy::Minor);
+        assert_eq!(effective_policy(&conn, "Team-Name-Extra", None).unwrap(), UpdatePolicy::Hold);
+        assert_eq!(effective_policy(&conn, "Team-Name", Some("b")).unwrap(), UpdatePolicy::Hold);
+    }
+}
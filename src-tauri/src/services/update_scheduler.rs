@@ -0,0 +1,93 @@
+use crate::commands::{settings_operations, update_operations};
+use crate::error::{AppError, Result};
+use crate::models::{AppSettings, UpdateInfo};
+use crate::services::thunderstore_service::ThunderstoreService;
+use crate::state::AppState;
+use std::collections::HashSet;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// Delay before the first check, so startup is not slowed down by the catalog download.
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+/// Lower bound for the configured interval to avoid hammering the Thunderstore API.
+const MIN_INTERVAL_MINUTES: u32 = 15;
+/// How often to re-read settings while background checks are disabled.
+const IDLE_POLL: Duration = Duration::from_secs(300);
+
+/// Starts the background update loop. Settings are re-read before every run, so toggling
+/// `auto_update` or changing the interval takes effect without a restart.
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        // Updates the user has already been told about, so the same ones are not re-notified
+        let mut notified: HashSet<String> = HashSet::new();
+
+        loop {
+            let settings = match settings_operations::load_settings(app.clone()).await {
+                Ok(settings) => settings,
+                Err(e) => {
+                    tracing::warn!("Background update check skipped, could not load settings: {}", e);
+                    tokio::time::sleep(IDLE_POLL).await;
+                    continue;
+                }
+            };
+
+            if !settings.auto_update {
+                tokio::time::sleep(IDLE_POLL).await;
+                continue;
+            }
+
+            if let Err(e) = run_check(&app, &settings, &mut notified).await {
+                tracing::warn!("Background update check failed: {}", e);
+            }
+
+            let minutes = settings.update_check_interval_minutes.max(MIN_INTERVAL_MINUTES);
+            tokio::time::sleep(Duration::from_secs(u64::from(minutes) * 60)).await;
+        }
+    });
+}
+
+async fn run_check(app: &AppHandle, settings: &AppSettings, notified: &mut HashSet<String>) -> Result<()> {
+    tracing::info!("Running background update check");
+    let state = app.state::<AppState>();
+
+    ThunderstoreService::new(state.db.clone()).fetch_and_cache_mods().await?;
+    let report = update_operations::check_updates(state.clone()).await?;
+    app.emit("updates-checked", &report).map_err(|e| AppError::Custom(e.to_string()))?;
+
+    let fresh: Vec<_> = report
+        .updates
+        .iter()
+        .filter(|u| !notified.contains(&format!("{}@{}", u.mod_id, u.latest_version)))
+        .collect();
+    if fresh.is_empty() {
+        return Ok(());
+    }
+
+    // Only packages the update policies allow end up in `report.updates`, held ones never do
+    let auto_apply = settings.auto_apply_updates && !settings.repository_path.is_empty();
+    if auto_apply {
+        update_operations::update_all_mods(app.clone(), state.clone(), settings.repository_path.clone()).await?;
+        notify(app, "Mods updated", &summary(&fresh, "updated"));
+    } else {
+        notify(app, "Mod updates available", &summary(&fresh, "can be updated"));
+    }
+
+    notified.extend(fresh.iter().map(|u| format!("{}@{}", u.mod_id, u.latest_version)));
+    Ok(())
+}
+
+fn summary(updates: &[&UpdateInfo], verb: &str) -> String {
+    match updates {
+        [single] => format!("{} {} to {}", single.mod_id, verb, single.latest_version),
+        _ => format!("{} mods {}", updates.len(), verb),
+    }
+}
+
+fn notify(app: &AppHandle, title: &str, body: &str) {
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        tracing::warn!("Failed to show notification: {}", e);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::error::Result;
+use crate::models::{SaveKind, SaveRef, ValheimSave};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Save folders below the IronGate data folder. Since the Hearth & Home update the game
+/// keeps local saves in `*_local`; the plain folders hold saves from older versions.
+const FOLDERS: &[(&str, SaveKind, bool)] = &[
+    ("worlds_local", SaveKind::World, false),
+    ("characters_local", SaveKind::Character, false),
+    ("worlds", SaveKind::World, true),
+    ("characters", SaveKind::Character, true),
+];
+/// Marks the timestamped copies Valheim keeps itself; they are not listed as saves.
+const GAME_BACKUP_MARKER: &str = "_backup_";
+
+/// Valheim's data folder (Unity's persistent data path for IronGate/Valheim) on this machine,
+/// including the Proton prefix on Linux.
+pub fn data_dir() -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+
+    #[cfg(target_os = "windows")]
+    if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join("AppData").join("LocalLow").join("IronGate").join("Valheim"));
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join(".config/unity3d/IronGate/Valheim"));
+        for steam in [".steam/steam", ".local/share/Steam"] {
+            candidates.push(
+                home.join(steam)
+                    .join("steamapps/compatdata/892970/pfx/drive_c/users/steamuser/AppData/LocalLow/IronGate/Valheim"),
+            );
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join("Library/Application Support/IronGate/Valheim"));
+    }
+
+    candidates.into_iter().find(|dir| FOLDERS.iter().any(|(folder, _, _)| dir.join(folder).is_dir()))
+}
+
+/// Folder of a save below the data folder, e.g. `worlds_local`.
+pub fn folder(kind: SaveKind, legacy: bool) -> &'static str {
+    FOLDERS
+        .iter()
+        .find(|(_, k, l)| *k == kind && *l == legacy)
+        .map(|(folder, _, _)| *folder)
+        .unwrap_or_default()
+}
+
+/// Kind of save a folder holds and whether it is a legacy folder.
+pub fn from_folder(folder: &str) -> Option<(SaveKind, bool)> {
+    FOLDERS.iter().find(|(f, _, _)| *f == folder).map(|(_, kind, legacy)| (*kind, *legacy))
+}
+
+/// Every world and character in the data folder.
+pub fn list(data_dir: &Path) -> Result<Vec<ValheimSave>> {
+    let mut saves = Vec::new();
+    for (folder, kind, legacy) in FOLDERS {
+        let dir = data_dir.join(folder);
+        if !dir.is_dir() {
+            continue;
+        }
+
+        let mut by_name: BTreeMap<String, (u64, Option<std::time::SystemTime>)> = BTreeMap::new();
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(name) = save_name(&file_name, *kind) else {
+                continue;
+            };
+            let meta = entry.metadata()?;
+            let (size, modified) = by_name.entry(name).or_default();
+            *size += meta.len();
+            *modified = (*modified).max(meta.modified().ok());
+        }
+
+        for (name, (size, modified)) in by_name {
+            saves.push(ValheimSave {
+                kind: *kind,
+                name,
+                legacy: *legacy,
+                size,
+                modified: modified.map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()).unwrap_or_default(),
+            });
+        }
+    }
+    Ok(saves)
+}
+
+/// Files making up a save: the world's `.fwl` and `.db` or the character's `.fch`, along
+/// with the `.old` copies the game writes next to them.
+pub fn files(data_dir: &Path, save: &SaveRef) -> Result<Vec<PathBuf>> {
+    let dir = data_dir.join(folder(save.kind, save.legacy));
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if path.is_file() && save_name(&file_name, save.kind).as_deref() == Some(save.name.as_str()) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Name of the save a file belongs to, if it is one of the save's own files.
+pub fn save_name(file_name: &str, kind: SaveKind) -> Option<String> {
+    let (name, extension) = file_name.split_once('.')?;
+    let extension = extension.trim_end_matches(".old");
+    let own = match kind {
+        SaveKind::World => extension == "fwl" || extension == "db",
+        SaveKind::Character => extension == "fch",
+    };
+    (own && !name.is_empty() && !name.contains(GAME_BACKUP_MARKER)).then(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_name_recognises_world_files() {
+        assert_eq!(save_name("Midgard.fwl", SaveKind::World).as_deref(), Some("Midgard"));
+        assert_eq!(save_name("Midgard.db", SaveKind::World).as_deref(), Some("Midgard"));
+        assert_eq!(save_name("Midgard.db.old", SaveKind::World).as_deref(), Some("Midgard"));
+        assert_eq!(save_name("Midgard.fch", SaveKind::World), None);
+    }
+
+    #[test]
+    fn save_name_recognises_character_files() {
+        assert_eq!(save_name("viking.fch", SaveKind::Character).as_deref(), Some("viking"));
+        assert_eq!(save_name("viking.fch.old", SaveKind::Character).as_deref(), Some("viking"));
+        assert_eq!(save_name("viking.fwl", SaveKind::Character), None);
+    }
+
+    #[test]
+    fn save_name_skips_game_backups_and_other_files() {
+        assert_eq!(save_name("Midgard_backup_auto-20240101120000.db", SaveKind::World), None);
+        assert_eq!(save_name(".fwl", SaveKind::World), None);
+        assert_eq!(save_name("Midgard", SaveKind::World), None);
+        assert_eq!(save_name("Midgard.txt", SaveKind::World), None);
+    }
+
+    #[test]
+    fn list_and_files_group_a_save_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let worlds = dir.path().join("worlds_local");
+        fs::create_dir_all(&worlds).unwrap();
+        for file in ["Midgard.fwl", "Midgard.db", "Midgard.db.old", "Midgard_backup_auto-1.db", "notes.txt"] {
+            fs::write(worlds.join(file), b"data").unwrap();
+        }
+
+        let saves = list(dir.path()).unwrap();
+        assert_eq!(saves.len(), 1);
+        assert_eq!((saves[0].kind, saves[0].name.as_str(), saves[0].legacy), (SaveKind::World, "Midgard", false));
+        assert_eq!(saves[0].size, 12);
+
+        let save = SaveRef { kind: SaveKind::World, name: "Midgard".to_string(), legacy: false };
+        let names: Vec<String> = files(dir.path(), &save)
+            .unwrap()
+            .iter()
+            .map(|f| f.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["Midgard.db", "Midgard.db.old", "Midgard.fwl"]);
+    }
+}
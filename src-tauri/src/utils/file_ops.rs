@@ -0,0 +1,102 @@
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+#[cfg(target_os = "windows")]
+use walkdir::WalkDir;
+
+/// Links a package folder from the repository into a plugins folder. Uses a directory
+/// symlink, falling back to hardlinked copies on Windows without symlink privileges.
+pub fn link_dir(source: &Path, target: &Path) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        match std::os::windows::fs::symlink_dir(source, target) {
+            Ok(_) => Ok(()),
+            Err(_) => link_dir_contents(source, target),
+        }
+    }
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(source, target)
+    }
+}
+
+/// Removes a link created by `link_dir`. Dangling links are removed as well.
+pub fn unlink_dir(target: &Path) -> io::Result<()> {
+    if target.symlink_metadata().is_err() {
+        return Ok(());
+    }
+
+    #[cfg(unix)]
+    {
+        fs::remove_file(target).or_else(|_| fs::remove_dir_all(target))
+    }
+    #[cfg(target_os = "windows")]
+    {
+        fs::remove_dir_all(target)
+    }
+}
+
+/// Returns true if `entry` is a symlink (dangling or not) pointing inside `root`.
+pub fn is_link_into(entry: &Path, root: &Path) -> bool {
+    let Ok(meta) = entry.symlink_metadata() else {
+        return false;
+    };
+    if !meta.file_type().is_symlink() {
+        return false;
+    }
+    fs::read_link(entry).is_ok_and(|target| target.starts_with(root))
+}
+
+/// Recursively copies a directory, creating `dst` as needed.
+pub fn copy_dir_all(src: &Path, dst: &Path) -> io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes files keyed by their path relative to `root`, replacing existing ones.
+pub fn write_files(root: &Path, files: &[(PathBuf, Vec<u8>)]) -> io::Result<()> {
+    for (relative, content) in files {
+        let target = root.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, content)?;
+    }
+    Ok(())
+}
+
+/// Turns an archive entry name into a relative path, rejecting absolute paths and `..`
+/// so entries cannot escape the folder they are extracted to.
+pub fn safe_relative_path(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    let safe = !name.is_empty() && path.components().all(|c| matches!(c, Component::Normal(_)));
+    safe.then(|| path.to_path_buf())
+}
+
+// Helper for recursive hardlinking (Windows fallback)
+#[cfg(target_os = "windows")]
+fn link_dir_contents(src: &Path, dst: &Path) -> io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in WalkDir::new(src) {
+        let entry = entry?;
+        let path = entry.path();
+        let rel_path = path.strip_prefix(src).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let target_path = dst.join(rel_path);
+
+        if path.is_dir() {
+            fs::create_dir_all(&target_path)?;
+        } else {
+            fs::hard_link(path, &target_path)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Hex-encoded SHA-256 of a byte slice.
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Hex-encoded SHA-256 over the files below `dir`. Each file contributes its relative path
+/// (with `/` separators) and the hash of its contents, in path order, so the result does not
+/// depend on the platform or directory iteration order.
+pub fn hash_dir(dir: &Path) -> io::Result<String> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir).follow_links(true) {
+        let entry = entry.map_err(io::Error::other)?;
+        if entry.file_type().is_file() {
+            let relative = entry.path().strip_prefix(dir).map_err(io::Error::other)?;
+            let name = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((name, entry.into_path()));
+        }
+    }
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for (name, path) in files {
+        let mut file_hasher = Sha256::new();
+        io::copy(&mut File::open(&path)?, &mut file_hasher)?;
+        hasher.update(name.as_bytes());
+        hasher.update([0]);
+        hasher.update(file_hasher.finalize());
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
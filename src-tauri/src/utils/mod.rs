@@ -1,3 +1,4 @@
 pub mod file_ops;
 pub mod validation;
-pub mod hash;
\ No newline at end of file
+pub mod hash;
+pub mod version;
\ No newline at end of file
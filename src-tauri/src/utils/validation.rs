@@ -0,0 +1,19 @@
+use regex::Regex;
+
+pub const MAX_PROFILE_NAME_LEN: usize = 64;
+
+/// Profile colors are CSS hex colors (`#rgb`, `#rrggbb` or `#rrggbbaa`); empty means default.
+pub fn is_valid_color(color: &str) -> bool {
+    if color.is_empty() {
+        return true;
+    }
+    Regex::new(r"^#(?:[0-9a-fA-F]{3}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$")
+        .map(|re| re.is_match(color))
+        .unwrap_or(false)
+}
+
+/// Trims a profile name and checks it is non-empty and not overly long.
+pub fn normalize_profile_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    (!name.is_empty() && name.chars().count() <= MAX_PROFILE_NAME_LEN).then(|| name.to_string())
+}
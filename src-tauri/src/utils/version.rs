@@ -0,0 +1,25 @@
+use semver::Version;
+
+/// Splits an installed package id ("Team-Name-1.2.3") into its package name
+/// ("Team-Name") and version ("1.2.3"). Returns `None` for catalog ids that
+/// carry no version suffix.
+pub fn split_package_id(id: &str) -> Option<(&str, &str)> {
+    let (package, version) = id.rsplit_once('-')?;
+    parse(version)?;
+    Some((package, version))
+}
+
+/// Parses a Thunderstore version number. Thunderstore enforces plain
+/// `major.minor.patch`, but tolerate a leading `v` used in some changelogs.
+pub fn parse(version: &str) -> Option<Version> {
+    Version::parse(version.trim().trim_start_matches(['v', 'V'])).ok()
+}
+
+/// Returns true if `candidate` is a strictly newer version than `current`.
+/// Unparseable versions fall back to inequality, matching the old behaviour.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    match (parse(candidate), parse(current)) {
+        (Some(c), Some(i)) => c > i,
+        _ => candidate != current,
+    }
+}
@@ -21,12 +21,52 @@ export const tauriCommands = {
   // Profile operations
   createProfile: (profile: any) => invoke<any>("create_profile", { profile }),
   updateProfile: (id: string, updates: any) =>
-    invoke<void>("update_profile", { id, updates }),
-  deleteProfile: (id: string) => invoke<void>("delete_profile", { id }),
-  switchProfile: (id: string) => invoke<void>("switch_profile", { id }),
+    invoke<any>("update_profile", { id, updates }),
+  deleteProfile: (id: string, switchTo?: string) => invoke<void>("delete_profile", { id, switchTo }),
+  switchProfile: (id: string) => invoke<any>("switch_profile", { id }),
+  duplicateProfile: (id: string, name: string) => invoke<any>("duplicate_profile", { id, name }),
+  saveProfileAsTemplate: (profileId: string, name: string) =>
+    invoke<any>("save_profile_as_template", { profileId, name }),
+  createProfileFromTemplate: (templateId: string, name: string) =>
+    invoke<any>("create_profile_from_template", { templateId, name }),
+  listProfileTemplates: () => invoke<any[]>("list_profile_templates"),
+  deleteProfileTemplate: (id: string) => invoke<void>("delete_profile_template", { id }),
   listProfiles: () => invoke<any[]>("list_profiles"),
+  getProfile: (id: string) => invoke<any>("get_profile", { id }),
+  getProfileBepinexPath: (profileId: string) => invoke<string>("get_profile_bepinex_path", { profileId }),
   exportProfileToCode: (profileId: string) => invoke<string>("export_profile_to_code", { profileId }),
+  diffProfiles: (leftId: string, rightId: string) => invoke<any>("diff_profiles", { leftId, rightId }),
+  diffProfileWithCode: (profileId: string, code: string) =>
+    invoke<any>("diff_profile_with_code", { profileId, code }),
+  mergeProfiles: (sourceId: string, targetId: string, packages: string[], configs: any[]) =>
+    invoke<any>("merge_profiles", { sourceId, targetId, packages, configs }),
+  listProfileHistory: (profileId: string) => invoke<any[]>("list_profile_history", { profileId }),
+  revertProfile: (profileId: string, historyId: number) =>
+    invoke<any>("revert_profile", { profileId, historyId }),
+  undoProfileChange: (profileId: string) => invoke<any>("undo_profile_change", { profileId }),
+  validateProfile: (profileId: string) => invoke<any>("validate_profile", { profileId }),
+  previewProfileImport: (code: string) => invoke<any>("preview_profile_import", { code }),
   importProfileFromCode: (code: string, newName: string) => invoke<any>("import_profile_from_code", { code, newName }),
+  createProfileFromServer: (serverPath: string, newName: string) =>
+    invoke<any>("create_profile_from_server", { serverPath, newName }),
+  importR2z: (path: string, newName?: string) => invoke<any>("import_r2z", { path, newName }),
+  importR2modmanCode: (code: string, newName?: string) =>
+    invoke<any>("import_r2modman_code", { code, newName }),
+  exportR2z: (profileId: string, path: string) => invoke<void>("export_r2z", { profileId, path }),
+  exportR2modmanCode: (profileId: string) => invoke<string>("export_r2modman_code", { profileId }),
+  detectR2modmanProfiles: (dataDir?: string) => invoke<any[]>("detect_r2modman_profiles", { dataDir }),
+  migrateR2modman: (dataDir?: string, profiles?: string[]) =>
+    invoke<any[]>("migrate_r2modman", { dataDir, profiles }),
+  listProfileConfigs: (profileId: string) => invoke<string[]>("list_profile_configs", { profileId }),
+  exportProfileBundle: (profileId: string, path: string, configs?: string[]) =>
+    invoke<void>("export_profile_bundle", { profileId, path, configs }),
+  inspectProfileBundle: (path: string) => invoke<any>("inspect_profile_bundle", { path }),
+  importProfileBundle: (path: string, overwrite: string[], newName?: string) =>
+    invoke<any>("import_profile_bundle", { path, newName, overwrite }),
+  lockProfile: (profileId: string) => invoke<any>("lock_profile", { profileId }),
+  getProfileLock: (profileId: string) => invoke<any>("get_profile_lock", { profileId }),
+  checkProfileLock: (profileId: string) => invoke<any[]>("check_profile_lock", { profileId }),
+  syncProfileLock: (profileId: string) => invoke<any>("sync_profile_lock", { profileId }),
 
   // System operations
   detectValheimPath: () => invoke<string>("detect_valheim_path"),
@@ -34,18 +74,37 @@ export const tauriCommands = {
   installBepinex: () => invoke<void>("install_bepinex"),
   launchValheim: (profileId?: string) =>
     invoke<void>("launch_valheim", { profileId }),
+  listPlaySessions: (profileId?: string, limit?: number) =>
+    invoke<any[]>("list_play_sessions", { profileId, limit }),
 
   // Update operations
-  checkUpdates: () => invoke<any[]>("check_updates"),
-  updateMod: (repositoryPath: string, modId: string) => invoke<void>("update_mod", { repositoryPath, modId }),
+  checkUpdates: () => invoke<any>("check_updates"),
+  checkProfileUpdates: (profileId: string) => invoke<any>("check_profile_updates", { profileId }),
+  analyzeUpdateImpact: (profileId?: string) => invoke<any[]>("analyze_update_impact", { profileId }),
+  updateMod: (repositoryPath: string, modId: string, profileId?: string) =>
+    invoke<void>("update_mod", { repositoryPath, modId, profileId }),
   updateAllMods: (repositoryPath: string) => invoke<void>("update_all_mods", { repositoryPath }),
+  setUpdatePolicy: (rule: any) => invoke<void>("set_update_policy", { rule }),
+  removeUpdatePolicy: (profileId?: string, packageName?: string) =>
+    invoke<void>("remove_update_policy", { profileId, packageName }),
+  listUpdatePolicies: () => invoke<any[]>("list_update_policies"),
+  listUpdateHistory: () => invoke<any[]>("list_update_history"),
+  rollbackUpdate: (repositoryPath: string, historyId: number) =>
+    invoke<void>("rollback_update", { repositoryPath, historyId }),
 
   // Backup operations
   createBackup: (description?: string) =>
-    invoke<void>("create_backup", { description }),
+    invoke<any>("create_backup", { description }),
   restoreBackup: (backupId: string) =>
-    invoke<void>("restore_backup", { backupId }),
-  listBackups: () => invoke<any[]>("list_backups"),
+    invoke<any>("restore_backup", { backupId }),
+  listBackups: () => invoke<any>("list_backups"),
+  deleteBackup: (backupId: string) => invoke<any>("delete_backup", { backupId }),
+  listValheimSaves: () => invoke<any[]>("list_valheim_saves"),
+  backupValheimSaves: (saves?: any[], description?: string) =>
+    invoke<any>("backup_valheim_saves", { saves, description }),
+  listBackupSaves: (backupId: string) => invoke<any[]>("list_backup_saves", { backupId }),
+  restoreValheimSaves: (backupId: string, saves: any[]) =>
+    invoke<any>("restore_valheim_saves", { backupId, saves }),
 
   // Settings operations
   saveSettings: (settings: any) => invoke<void>("save_settings", { settings }),
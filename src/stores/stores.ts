@@ -34,6 +34,17 @@ export interface Profile {
   created: string;
   lastUsed: string;
   playTime: number;
+  modEntries?: ProfileMod[];
+}
+
+export interface ProfileMod {
+  modId: string;
+  packageName: string;
+  name: string;
+  version: string;
+  enabled: boolean;
+  installed: boolean;
+  missing: boolean;
 }
 
 export interface AppSettings {
@@ -45,6 +56,20 @@ export interface AppSettings {
   autoUpdate: boolean;
   autoBackup: boolean;
   language: string;
+  updateRetentionDays?: number;
+  updateCheckIntervalMinutes?: number;
+  autoApplyUpdates?: boolean;
+  isolatedProfiles?: boolean;
+  profileCodeEndpoint?: string;
+  valheimDataPath?: string;
+  backupIntervalHours?: number;
+  backupOnLaunch?: boolean;
+  backupOnExit?: boolean;
+  backupIncludeSaves?: boolean;
+  backupKeepLast?: number;
+  backupKeepDailyDays?: number;
+  backupKeepWeeklyWeeks?: number;
+  backupMaxTotalMb?: number;
 }
 
 // ============================================================================